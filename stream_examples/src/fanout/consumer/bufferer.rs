@@ -6,7 +6,7 @@ pub(crate) struct Bufferer;
 impl fanout::consumer::FanoutConsumer for Bufferer {
     type Item = bytes::Bytes;
     type Output = Vec<bytes::Bytes>;
-    type Error = std::convert::Infallible;
+    type Error = crate::fanout::Error;
     async fn consume_from_fanout<Rx>(
         &self,
         mut rx: Rx,
@@ -24,6 +24,16 @@ impl fanout::consumer::FanoutConsumer for Bufferer {
         while let Some(chunk) = rx.recv().await {
             buffer.push(chunk);
         }
+        // don't hand out a truncated buffer as if it were complete
+        match rx.completion() {
+            Some(stream_utils::broadcaster::Completion::Failed) => {
+                return Err(crate::fanout::Error("source failed partway through".to_string()));
+            }
+            Some(stream_utils::broadcaster::Completion::Evicted) => {
+                return Err(crate::fanout::Error("evicted for falling behind".to_string()));
+            }
+            _ => {}
+        }
         Ok(buffer)
     }
 }
@@ -31,8 +31,9 @@ impl fanout::consumer::FanoutConsumer for BytesCounter {
         while let Some(bytes) = rx.recv().await {
             i += bytes.len();
             if i > self.limit {
-                cancellation_token.cancel();
-                return Err(crate::fanout::Error("too big".to_string()));
+                let error = crate::fanout::Error("too big".to_string());
+                cancellation_token.cancel_with_error(error.clone());
+                return Err(error);
             }
         }
         Ok(i)
@@ -17,7 +17,7 @@ macro_rules! test_channel {
 
 async fn test_nonsend_channel<Channel>(channel: Channel)
 where
-    Channel: stream_utils::channel::Channel<Item = bytes::Bytes> + Clone + 'static,
+    Channel: stream_utils::channel::Channel<Item = bytes::Bytes, Shared = bytes::Bytes> + Clone + 'static,
     Channel::Receiver: 'static,
 {
     run(channel.clone()).await; // running the future directly should always work
@@ -39,7 +39,7 @@ pub(super) async fn main() {
 
 async fn run<Channel>(channel: Channel)
 where
-    Channel: stream_utils::channel::Channel<Item = bytes::Bytes> + Clone,
+    Channel: stream_utils::channel::Channel<Item = bytes::Bytes, Shared = bytes::Bytes> + Clone,
     Channel::Receiver: 'static,
 {
     // testing that an aribtrary number of consumers works
@@ -63,29 +63,28 @@ where
         .bufferer(&bufferer)
         .build();
 
-    let mut fanouts = Vec::with_capacity(3);
-
-    fanouts.push(fanout::StreamFanout::new(
-        source::Source::from(source::buffer::BytesSource::from(vec![
-            bytes::Bytes::from_static(&[0u8; 10]),
-            bytes::Bytes::from_static(&[0u8; 20]),
-            bytes::Bytes::from_static(&[0u8; 20]),
-        ])),
-        download_fanout_consumers.clone(),
-    ));
-    fanouts.push(fanout::StreamFanout::new(
-        source::Source::from(source::buffer::BytesSource::from(vec![
-            bytes::Bytes::from_static(&[0u8; 10]),
-        ])),
-        download_fanout_consumers.clone(),
-    ));
-
-    fanouts.push(fanout::StreamFanout::new(
-        source::Source::from(source::url::UrlSource::from_url(
-            "https://thehive.ai/".to_string(),
-        )),
-        download_fanout_consumers.clone(),
-    ));
+    let mut fanouts = vec![
+        fanout::StreamFanout::new(
+            source::Source::from(source::buffer::BytesSource::from(vec![
+                bytes::Bytes::from_static(&[0u8; 10]),
+                bytes::Bytes::from_static(&[0u8; 20]),
+                bytes::Bytes::from_static(&[0u8; 20]),
+            ])),
+            download_fanout_consumers.clone(),
+        ),
+        fanout::StreamFanout::new(
+            source::Source::from(source::buffer::BytesSource::from(vec![
+                bytes::Bytes::from_static(&[0u8; 10]),
+            ])),
+            download_fanout_consumers.clone(),
+        ),
+        fanout::StreamFanout::new(
+            source::Source::from(fanout::source::HttpSource::new(
+                "https://thehive.ai/".parse().unwrap(),
+            )),
+            download_fanout_consumers.clone(),
+        ),
+    ];
 
     let mut i = 0;
     let result = loop {
@@ -137,7 +136,7 @@ async fn attempt_download<'a, Channel>(
     >,
 )
 where
-    Channel: stream_utils::channel::Channel<Item = bytes::Bytes> + Clone,
+    Channel: stream_utils::channel::Channel<Item = bytes::Bytes, Shared = bytes::Bytes> + Clone,
     Channel::Receiver: 'static,
 {
     let results = futures::future::join_all(fanouts.into_iter().map(|fanout| {
@@ -145,6 +144,7 @@ where
             .into_driver()
             .with_broadcaster_channel(channel.clone())
             .with_broadcaster_buffer_size(1)
+            .with_strict_content_length()
             .drive()
     }))
     .await;
@@ -153,20 +153,18 @@ where
     let mut retry_fanouts = Vec::with_capacity(downloads_count);
     let mut errors = Vec::with_capacity(downloads_count);
     for (used_download_fanout, result) in results {
+        if let Some(cancellation_reason) = used_download_fanout.cancellation_reason() {
+            println!("{cancellation_reason}");
+        }
         let (source, mut retry_consumers) = used_download_fanout.into_parts();
-        let mut retry_source = source.reset();
+        let retry_source = source.reset();
         match result {
             Ok(download_fanout_output) => {
-                if let Ok(buffer) = &download_fanout_output.bufferer {
-                    retry_source = Some(source::Source::from(source::buffer::BytesSource::from(
-                        buffer.clone(),
-                    )));
-                    retry_consumers = retry_consumers.retry(&download_fanout_output);
-                }
+                retry_consumers = retry_consumers.retry(&download_fanout_output);
                 outputs.push(download_fanout_output);
             }
             Err(error) => {
-                errors.push(error);
+                errors.push(error.into());
             }
         }
         if let Some(retry_source) = retry_source {
@@ -202,7 +200,7 @@ where
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Error(String);
 
 impl From<String> for Error {
@@ -211,8 +209,19 @@ impl From<String> for Error {
     }
 }
 
+impl From<fanout::FanoutError<Error>> for Error {
+    fn from(value: fanout::FanoutError<Error>) -> Self {
+        match value {
+            fanout::FanoutError::Source(error) => error,
+            error => Self(error.to_string()),
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
     }
 }
+
+impl std::error::Error for Error {}
@@ -1,11 +1,10 @@
 use stream_utils::fanout;
 
 pub mod buffer;
-pub mod url;
 
 pub(super) enum Source {
     Bytes(buffer::BytesSource),
-    Url(url::UrlSource),
+    Url(Box<fanout::source::Spooled<fanout::source::Retrying<fanout::source::HttpSource>>>),
 }
 
 impl From<buffer::BytesSource> for Source {
@@ -14,9 +13,13 @@ impl From<buffer::BytesSource> for Source {
     }
 }
 
-impl From<url::UrlSource> for Source {
-    fn from(value: url::UrlSource) -> Self {
-        Self::Url(value)
+impl From<fanout::source::HttpSource> for Source {
+    fn from(value: fanout::source::HttpSource) -> Self {
+        // network hiccups are retried within the fanout, resuming where the response broke off, and once the body is
+        // spooled, retries for the consumers' sake replay it instead of downloading it again
+        let policy = fanout::source::RetryPolicy::new(3);
+        let retrying = fanout::source::Retrying::new(value, policy).with_resume();
+        Self::Url(Box::new(fanout::source::Spooled::new(retrying)))
     }
 }
 
@@ -26,7 +29,10 @@ impl fanout::source::FanoutSource for Source {
     async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
         match self {
             Self::Bytes(bytes_source) => bytes_source.get_content_length().await,
-            Self::Url(url_source) => url_source.get_content_length().await,
+            Self::Url(url_source) => url_source
+                .get_content_length()
+                .await
+                .map_err(|e| e.to_string().into()),
         }
     }
     async fn broadcast<Channel>(
@@ -38,13 +44,16 @@ impl fanout::source::FanoutSource for Source {
     {
         match self {
             Self::Bytes(bytes_source) => bytes_source.broadcast(broadcaster).await,
-            Self::Url(url_source) => url_source.broadcast(broadcaster).await,
+            Self::Url(url_source) => url_source
+                .broadcast(broadcaster)
+                .await
+                .map_err(|e| e.to_string().into()),
         }
     }
     fn reset(self) -> Option<Self> {
         match self {
             Self::Bytes(bytes_source) => bytes_source.reset().map(Self::Bytes),
-            Self::Url(url_source) => url_source.reset().map(Self::Url),
+            Self::Url(url_source) => (*url_source).reset().map(Box::new).map(Self::Url),
         }
     }
 }
@@ -0,0 +1,92 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use futures::future::join_all;
+use stream_utils::{
+    broadcaster::{Broadcaster, Delivery},
+    channel,
+};
+
+const ITEMS: u64 = 1_000;
+const BUFFER_SIZE: usize = 16;
+const SUBSCRIBER_COUNTS: [usize; 3] = [1, 8, 64];
+const DELIVERIES: [Delivery; 3] = [Delivery::Concurrent, Delivery::Unordered, Delivery::Sequential];
+
+/// Counts allocations, so each delivery mode's allocations per item can be printed next to its timings.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+async fn broadcast_and_drain<Channel>(channel: Channel, delivery: Delivery, subscriber_count: usize)
+where
+    Channel: channel::Channel<Item = u64>,
+{
+    let mut broadcaster = Broadcaster::builder()
+        .channel(channel)
+        .buffer_size(BUFFER_SIZE)
+        .delivery(delivery)
+        .build();
+    let mut rxs: Vec<_> = (0..subscriber_count).map(|_| broadcaster.subscribe()).collect();
+    tokio::join!(
+        async move {
+            for item in 0..ITEMS {
+                broadcaster.broadcast(item).await.unwrap();
+            }
+        },
+        join_all(rxs.iter_mut().map(|rx| async { while rx.recv().await.is_some() {} })),
+    );
+}
+
+fn bench_backend<Channel>(c: &mut Criterion, backend: &str, channel: Channel)
+where
+    Channel: channel::Channel<Item = u64> + Copy,
+{
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let mut group = c.benchmark_group(format!("delivery/{backend}"));
+    group.throughput(criterion::Throughput::Elements(ITEMS));
+    for subscriber_count in SUBSCRIBER_COUNTS {
+        for delivery in DELIVERIES {
+            let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+            runtime.block_on(broadcast_and_drain(channel, delivery, subscriber_count));
+            let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+            println!(
+                "delivery/{backend}/{delivery:?}/{subscriber_count}: {:.1} allocations per item",
+                allocations as f64 / ITEMS as f64
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("{delivery:?}"), subscriber_count),
+                &subscriber_count,
+                |b, &subscriber_count| {
+                    b.to_async(&runtime)
+                        .iter(|| broadcast_and_drain(channel, delivery, subscriber_count))
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn delivery(c: &mut Criterion) {
+    bench_backend(c, "tokio", tokio::sync::mpsc::channel::<u64>);
+    bench_backend(c, "kanal", kanal::bounded_async::<u64>);
+    bench_backend(c, "crossfire", crossfire::spsc::bounded_async::<u64>);
+}
+
+criterion_group!(benches, delivery);
+criterion_main!(benches);
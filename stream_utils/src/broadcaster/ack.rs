@@ -0,0 +1,106 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use super::SubscriberId;
+
+/// How far a subscriber has acknowledged, as the number of items it has processed.
+///
+/// This is also the sequence number of the first item the subscriber still needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Acknowledgement {
+    pub subscriber_id: SubscriberId,
+    pub acknowledged: u64,
+}
+
+#[derive(Debug)]
+struct SubscriberAcknowledgement {
+    subscriber_id: SubscriberId,
+    acknowledged: Arc<AtomicU64>,
+    // set once the subscriber is gone, so it no longer holds back the low watermark
+    detached: bool,
+}
+
+type SubscriberAcknowledgements = Vec<SubscriberAcknowledgement>;
+
+/// Tracks the acknowledgements of every subscriber of a broadcaster in ack mode.
+///
+/// Clones share the same state, so a tracker taken before handing the broadcaster off keeps reporting afterwards.
+/// Subscribers that are gone (dropped, evicted or taken apart with `Subscription::into_inner`) keep their last
+/// acknowledgement, but no longer count towards the low watermark.
+#[derive(Debug, Clone, Default)]
+pub struct AckTracker {
+    subscribers: Arc<Mutex<SubscriberAcknowledgements>>,
+}
+
+impl AckTracker {
+    pub(super) fn register(&self, subscriber_id: SubscriberId, first_sequence: u64) -> AckSubscriber {
+        let acknowledged = Arc::new(AtomicU64::new(first_sequence));
+        self.lock().push(SubscriberAcknowledgement {
+            subscriber_id,
+            acknowledged: acknowledged.clone(),
+            detached: false,
+        });
+        AckSubscriber {
+            ack_tracker: self.clone(),
+            subscriber_id,
+            acknowledged,
+        }
+    }
+
+    pub(super) fn detach(&self, subscriber_id: SubscriberId) {
+        if let Some(subscriber) = self
+            .lock()
+            .iter_mut()
+            .find(|subscriber| subscriber.subscriber_id == subscriber_id)
+        {
+            subscriber.detached = true;
+        }
+    }
+
+    pub fn acknowledgements(&self) -> Vec<Acknowledgement> {
+        self.lock()
+            .iter()
+            .map(|subscriber| Acknowledgement {
+                subscriber_id: subscriber.subscriber_id,
+                acknowledged: subscriber.acknowledged.load(Ordering::Acquire),
+            })
+            .collect()
+    }
+
+    /// The number of items every remaining subscriber has acknowledged, or `None` without subscribers.
+    pub fn low_watermark(&self) -> Option<u64> {
+        self.lock()
+            .iter()
+            .filter(|subscriber| !subscriber.detached)
+            .map(|subscriber| subscriber.acknowledged.load(Ordering::Acquire))
+            .min()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SubscriberAcknowledgements> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A subscriber's acknowledgements, which detaches it from the tracker once dropped.
+#[derive(Debug)]
+pub(super) struct AckSubscriber {
+    ack_tracker: AckTracker,
+    subscriber_id: SubscriberId,
+    acknowledged: Arc<AtomicU64>,
+}
+
+impl AckSubscriber {
+    pub(super) fn acknowledge(&self, acknowledged: u64) {
+        self.acknowledged.fetch_max(acknowledged, Ordering::AcqRel);
+    }
+}
+
+impl Drop for AckSubscriber {
+    fn drop(&mut self) {
+        self.ack_tracker.detach(self.subscriber_id);
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+};
+
+use super::{MemoryBudget, SubscriberId};
+
+/// How many consecutive sends a subscriber has to keep up with before its buffer shrinks.
+const SHRINK_AFTER: usize = 32;
+
+/// Sizes each subscriber's buffer between bounds, based on how far the subscriber lags behind.
+///
+/// Every subscriber starts at `min` items. A subscriber that makes a send wait for room doubles its buffer, up to
+/// `max` and as far as the budget allows, while one that keeps its buffer at most a quarter full halves it again.
+/// The budget is in items, and each subscriber always gets `min`. Clones share the same budget, like
+/// [`MemoryBudget`], so one adaptive buffer attached to many broadcasters sizes all of their subscribers together.
+/// With a [`MemoryBudget`] attached to the broadcaster, a buffer also only grows by as many items as that budget has
+/// room for at the size of the item being sent, so buffers across every broadcaster sharing it grow with what it can
+/// actually hold.
+///
+/// Channels are created with room for `max` items, so the adaptive sizes apply on top of the channel's own bound.
+#[derive(Debug, Clone)]
+pub struct AdaptiveBuffer {
+    min: usize,
+    max: usize,
+    budget: usize,
+    // the items reserved by every subscriber sharing the budget
+    allocated: Arc<AtomicUsize>,
+}
+
+impl AdaptiveBuffer {
+    /// Without a budget, every subscriber can grow to `max`.
+    pub fn new(min: usize, max: usize) -> Self {
+        assert!(0 < min && min <= max, "adaptive buffer bounds must satisfy 0 < min <= max");
+        Self {
+            min,
+            max,
+            budget: usize::MAX,
+            allocated: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn with_budget(self, budget: usize) -> Self {
+        Self { budget, ..self }
+    }
+
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The items reserved right now, across every broadcaster sharing the budget.
+    pub fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Acquire)
+    }
+}
+
+/// The items a subscriber has received, shared between its [`super::Subscription`] and the broadcaster.
+#[derive(Debug, Default)]
+struct ReceivedItems {
+    count: AtomicU64,
+    notify: tokio::sync::Notify,
+    // set once the subscription stops counting, e.g. when its receiver is taken out of it
+    detached: AtomicBool,
+}
+
+/// The subscription's side of [`ReceivedItems`]; the buffer stops limiting sends once it's dropped, since nothing
+/// would count what's received after that.
+#[derive(Debug)]
+pub(super) struct ReceivedTracker(Arc<ReceivedItems>);
+
+impl ReceivedTracker {
+    /// `received` counts every item since the subscription started, including any the channel skipped.
+    pub(super) fn record(&self, received: u64) {
+        self.0.count.fetch_max(received, Ordering::AcqRel);
+        self.0.notify.notify_one();
+    }
+}
+
+impl Drop for ReceivedTracker {
+    fn drop(&mut self) {
+        self.0.detached.store(true, Ordering::Release);
+        self.0.notify.notify_one();
+    }
+}
+
+/// The broadcaster's side of a subscriber's adaptive buffer.
+#[derive(Debug)]
+pub(super) struct SubscriberBuffer {
+    config: AdaptiveBuffer,
+    received: Arc<ReceivedItems>,
+    sent: AtomicU64,
+    capacity: AtomicUsize,
+    calm_sends: AtomicUsize,
+    // only reported through tracing
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    subscriber_id: SubscriberId,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    peak_capacity: AtomicUsize,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    blocked_nanos: AtomicU64,
+}
+
+impl SubscriberBuffer {
+    pub(super) fn new(config: AdaptiveBuffer, subscriber_id: SubscriberId) -> (Self, ReceivedTracker) {
+        config.allocated.fetch_add(config.min, Ordering::AcqRel);
+        let received = Arc::new(ReceivedItems::default());
+        let buffer = Self {
+            received: received.clone(),
+            subscriber_id,
+            sent: AtomicU64::new(0),
+            capacity: AtomicUsize::new(config.min),
+            peak_capacity: AtomicUsize::new(config.min),
+            calm_sends: AtomicUsize::new(0),
+            blocked_nanos: AtomicU64::new(0),
+            config,
+        };
+        (buffer, ReceivedTracker(received))
+    }
+
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Acquire)
+    }
+
+    /// Waits until the subscriber has room for another item, then resizes its buffer based on the wait.
+    ///
+    /// `memory` is the broadcaster's memory budget, if it has one, and the size of the item being sent.
+    pub(super) async fn reserve(&self, memory: Option<(&MemoryBudget, usize)>) {
+        let mut blocked_since = None;
+        let lag = loop {
+            // only this subscriber's sends reserve room, and those run one at a time
+            let lag = self.lag();
+            if lag < self.capacity() as u64 || self.received.detached.load(Ordering::Acquire) {
+                break lag;
+            }
+            blocked_since.get_or_insert_with(tokio::time::Instant::now);
+            self.received.notify.notified().await;
+        };
+        self.sent.fetch_add(1, Ordering::AcqRel);
+
+        match blocked_since {
+            Some(blocked_since) => {
+                self.blocked_nanos.fetch_add(
+                    blocked_since.elapsed().as_nanos().try_into().unwrap_or(u64::MAX),
+                    Ordering::AcqRel,
+                );
+                self.calm_sends.store(0, Ordering::Release);
+                self.grow(memory);
+            }
+            None if lag <= self.capacity() as u64 / 4 => {
+                if self.calm_sends.fetch_add(1, Ordering::AcqRel) + 1 >= SHRINK_AFTER {
+                    self.calm_sends.store(0, Ordering::Release);
+                    self.shrink();
+                }
+            }
+            None => self.calm_sends.store(0, Ordering::Release),
+        }
+    }
+
+    fn lag(&self) -> u64 {
+        self.sent
+            .load(Ordering::Acquire)
+            .saturating_sub(self.received.count.load(Ordering::Acquire))
+    }
+
+    fn grow(&self, memory: Option<(&MemoryBudget, usize)>) {
+        let capacity = self.capacity();
+        let mut wanted = std::cmp::min(capacity * 2, self.config.max) - capacity;
+        if let Some((memory_budget, item_bytes)) = memory {
+            wanted = std::cmp::min(wanted, memory_budget.available() / std::cmp::max(item_bytes, 1));
+        }
+        // take as much of the growth as the budget has left
+        let granted = self
+            .config
+            .allocated
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |allocated| {
+                let available = self.config.budget.saturating_sub(allocated);
+                (available > 0 && wanted > 0).then(|| allocated + std::cmp::min(wanted, available))
+            })
+            .map(|allocated| std::cmp::min(wanted, self.config.budget - allocated))
+            .unwrap_or(0);
+        if granted > 0 {
+            self.capacity.store(capacity + granted, Ordering::Release);
+            self.peak_capacity.fetch_max(capacity + granted, Ordering::AcqRel);
+        }
+    }
+
+    fn shrink(&self) {
+        let capacity = self.capacity();
+        let shrunk = std::cmp::max(capacity / 2, self.config.min);
+        if shrunk < capacity {
+            self.capacity.store(shrunk, Ordering::Release);
+            self.config.allocated.fetch_sub(capacity - shrunk, Ordering::AcqRel);
+        }
+    }
+}
+
+impl Drop for SubscriberBuffer {
+    fn drop(&mut self) {
+        let capacity = self.capacity();
+        self.config.allocated.fetch_sub(capacity, Ordering::AcqRel);
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            subscriber_id = self.subscriber_id.index(),
+            final_buffer_size = capacity,
+            peak_buffer_size = self.peak_capacity.load(Ordering::Acquire),
+            blocked_ms = self.blocked_nanos.load(Ordering::Acquire) / 1_000_000,
+            "adaptive buffer size",
+        );
+    }
+}
@@ -0,0 +1,86 @@
+/// Items with a size in bytes, e.g. [`bytes::Bytes`].
+///
+/// Byte-based options such as [`super::RateLimit::bytes_per_second`] are only available for these items.
+pub trait ByteLen {
+    fn byte_len(&self) -> usize;
+}
+
+impl ByteLen for bytes::Bytes {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ByteLen for bytes::BytesMut {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ByteLen for [u8] {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ByteLen for Vec<u8> {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ByteLen for str {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ByteLen for String {
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: ByteLen + ?Sized> ByteLen for &T {
+    fn byte_len(&self) -> usize {
+        (**self).byte_len()
+    }
+}
+
+impl<T: ByteLen + ?Sized> ByteLen for Box<T> {
+    fn byte_len(&self) -> usize {
+        (**self).byte_len()
+    }
+}
+
+impl<T: ByteLen + ?Sized> ByteLen for std::sync::Arc<T> {
+    fn byte_len(&self) -> usize {
+        (**self).byte_len()
+    }
+}
+
+/// Items that can be split at a byte offset, so a resumed broadcast can line them up with where each subscriber left
+/// off; see [`super::ProgressTracker::with_resumed_offsets`].
+pub trait SplitBytes: ByteLen + Sized {
+    /// Splits off and returns the first `at` bytes, leaving the rest.
+    fn split_to(&mut self, at: usize) -> Self;
+}
+
+impl SplitBytes for bytes::Bytes {
+    fn split_to(&mut self, at: usize) -> Self {
+        bytes::Bytes::split_to(self, at)
+    }
+}
+
+impl SplitBytes for bytes::BytesMut {
+    fn split_to(&mut self, at: usize) -> Self {
+        bytes::BytesMut::split_to(self, at)
+    }
+}
+
+impl SplitBytes for Vec<u8> {
+    fn split_to(&mut self, at: usize) -> Self {
+        let rest = self.split_off(at);
+        std::mem::replace(self, rest)
+    }
+}
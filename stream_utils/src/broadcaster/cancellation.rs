@@ -0,0 +1,151 @@
+use std::sync::{Arc, OnceLock};
+
+/// Identifies a subscriber of a [`super::Broadcaster`], in the order the subscribers were added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubscriberId(pub(super) usize);
+
+impl SubscriberId {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl std::fmt::Display for SubscriberId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "subscriber {}", self.0)
+    }
+}
+
+/// Who cancelled a broadcast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Canceller {
+    Subscriber(SubscriberId),
+    /// The broadcaster itself, e.g. when a subscriber misses its [`super::SendDeadline`].
+    Broadcaster,
+    /// Anyone holding the broadcaster's own token, e.g. the caller driving a fanout.
+    #[default]
+    External,
+}
+
+/// Why a broadcast was cancelled.
+#[derive(Debug, Clone)]
+pub struct CancellationReason {
+    canceller: Canceller,
+    error: Option<Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+impl CancellationReason {
+    pub fn new(canceller: Canceller) -> Self {
+        Self {
+            canceller,
+            error: None,
+        }
+    }
+
+    pub fn with_error<E>(canceller: Canceller, error: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self {
+            canceller,
+            error: Some(Arc::new(error)),
+        }
+    }
+
+    pub fn canceller(&self) -> Canceller {
+        self.canceller
+    }
+
+    pub fn error(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.error.as_deref()
+    }
+}
+
+impl std::fmt::Display for CancellationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.canceller {
+            Canceller::Subscriber(subscriber_id) => write!(f, "cancelled by {subscriber_id}")?,
+            Canceller::Broadcaster => write!(f, "cancelled by the broadcaster")?,
+            Canceller::External => write!(f, "cancelled externally")?,
+        }
+        match &self.error {
+            Some(error) => write!(f, ": {error}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for CancellationReason {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error
+            .as_deref()
+            .map(|error| error as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// A cancellation token that remembers why it was cancelled.
+///
+/// Clones share the same underlying token and reason, but each clone cancels on behalf of its own [`Canceller`].
+/// The token handed out by [`super::Broadcaster::get_cancellation_token`] cancels as [`Canceller::External`], while
+/// the tokens handed out by [`super::Broadcaster::subscribe_with_cancellation_token`] cancel as the subscriber.
+#[derive(Default, Debug, Clone)]
+pub struct CancellationToken {
+    token: tokio_util::sync::CancellationToken,
+    reason: Arc<OnceLock<CancellationReason>>,
+    canceller: Canceller,
+}
+
+impl CancellationToken {
+    /// Cancels without an error; only the canceller is recorded.
+    pub fn cancel(&self) {
+        self.cancel_with_reason(CancellationReason::new(self.canceller));
+    }
+
+    pub fn cancel_with_error<E>(&self, error: E)
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.cancel_with_reason(CancellationReason::with_error(self.canceller, error));
+    }
+
+    /// Only the first reason is kept if the token is cancelled more than once.
+    pub fn cancel_with_reason(&self, reason: CancellationReason) {
+        // record the reason before waking anyone waiting on the token
+        let _ = self.reason.set(reason);
+        self.token.cancel();
+    }
+
+    /// Returns `None` until the token is cancelled.
+    pub fn reason(&self) -> Option<CancellationReason> {
+        match self.reason.get() {
+            Some(reason) => Some(reason.clone()),
+            // cancelled through the inner token directly, so nobody left a reason
+            None if self.token.is_cancelled() => Some(CancellationReason::new(Canceller::External)),
+            None => None,
+        }
+    }
+
+    pub(crate) fn for_subscriber(&self, subscriber_id: SubscriberId) -> Self {
+        Self {
+            token: self.token.clone(),
+            reason: self.reason.clone(),
+            canceller: Canceller::Subscriber(subscriber_id),
+        }
+    }
+}
+
+impl From<tokio_util::sync::CancellationToken> for CancellationToken {
+    fn from(value: tokio_util::sync::CancellationToken) -> Self {
+        Self {
+            token: value,
+            ..Default::default()
+        }
+    }
+}
+
+impl std::ops::Deref for CancellationToken {
+    type Target = tokio_util::sync::CancellationToken;
+    fn deref(&self) -> &Self::Target {
+        &self.token
+    }
+}
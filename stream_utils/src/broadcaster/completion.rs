@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use super::{CancellationReason, CancellationToken};
+
+/// How a broadcast ended, as seen by its subscribers once their channel is closed.
+#[derive(Debug, Clone)]
+pub enum Completion {
+    /// The source ran to the end.
+    Finished,
+    /// The source failed partway through, so the items received are truncated.
+    Failed,
+    /// The subscriber missed its [`super::SendDeadline`] and was evicted, so the items it received are truncated,
+    /// however the rest of the broadcast went.
+    Evicted,
+    Cancelled(CancellationReason),
+}
+
+impl Completion {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Finished)
+    }
+}
+
+/// Resolves the [`Completion`] of a broadcast.
+///
+/// Clones share the same state, and only the first resolution is kept.
+#[derive(Debug, Clone)]
+pub struct CompletionToken {
+    completion: Arc<tokio::sync::watch::Sender<Option<Completion>>>,
+    cancellation_token: CancellationToken,
+}
+
+impl CompletionToken {
+    pub(super) fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            completion: Arc::new(tokio::sync::watch::Sender::new(None)),
+            cancellation_token,
+        }
+    }
+
+    /// Resolves as [`Completion::Finished`], or as [`Completion::Cancelled`] if the broadcast was cancelled.
+    pub fn finish(&self) {
+        self.resolve(match self.cancellation_token.reason() {
+            Some(reason) => Completion::Cancelled(reason),
+            None => Completion::Finished,
+        });
+    }
+
+    pub fn fail(&self) {
+        self.resolve(Completion::Failed);
+    }
+
+    /// Returns `None` until resolved.
+    pub fn get(&self) -> Option<Completion> {
+        self.completion.borrow().clone()
+    }
+
+    pub async fn resolved(&self) -> Completion {
+        let mut completion = self.completion.subscribe();
+        // the sender lives as long as `self`, so this can't fail
+        let completion = completion
+            .wait_for(Option::is_some)
+            .await
+            .expect("completion sender outlives its receivers");
+        completion.clone().expect("waited for the completion to resolve")
+    }
+
+    fn resolve(&self, completion: Completion) {
+        self.completion.send_if_modified(|current| {
+            let unresolved = current.is_none();
+            if unresolved {
+                *current = Some(completion);
+            }
+            unresolved
+        });
+    }
+}
@@ -0,0 +1,55 @@
+use tokio::time::Duration;
+
+use super::SubscriberId;
+
+/// How long a subscriber gets to take each item before the broadcaster gives up on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendDeadline {
+    pub timeout: Duration,
+    pub action: DeadlineAction,
+}
+
+impl SendDeadline {
+    pub fn evict(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            action: DeadlineAction::Evict,
+        }
+    }
+
+    pub fn cancel(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            action: DeadlineAction::Cancel,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineAction {
+    /// Stop sending to the subscriber and carry on with the others.
+    ///
+    /// A subscriber that comes back gets the items it had already been sent, then the end of its stream, with
+    /// [`super::Completion::Evicted`] as its completion.
+    Evict,
+    /// Cancel the whole broadcast with a [`SendDeadlineExceeded`] error.
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SendDeadlineExceeded {
+    pub subscriber_id: SubscriberId,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for SendDeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} didn't take an item within {:?}",
+            self.subscriber_id, self.timeout
+        )
+    }
+}
+
+impl std::error::Error for SendDeadlineExceeded {}
@@ -0,0 +1,19 @@
+/// How a [`super::Broadcaster`] hands each item to its subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    /// Sends to every subscriber at once with `join_all`, polling every pending send whenever any of them wakes.
+    #[default]
+    Concurrent,
+    /// Sends to every subscriber at once through a `FuturesUnordered`, which only polls the sends that were woken.
+    ///
+    /// The `FuturesUnordered` is set up for each item, since the broadcaster can't hold on to send futures between
+    /// items, and it allocates a task per send: `benches/delivery.rs` prints the allocations per item, which only
+    /// drop below [`Self::Concurrent`]'s with dozens of subscribers. It pays for itself when a few slow subscribers
+    /// keep waking the broadcast while many others are already done.
+    Unordered,
+    /// Sends to one subscriber at a time, in the order they subscribed.
+    ///
+    /// Deterministic, and a subscriber never gets an item before the subscribers ahead of it, but a slow subscriber
+    /// delays everyone behind it.
+    Sequential,
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+
+use crate::channel;
+
+use super::{AckTracker, BroadcastError, Broadcaster, CancellationToken, PauseToken, ProgressTracker};
+
+/// The order subscribers see items in when several producers broadcast through clones of a [`BroadcastHandle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BroadcastOrdering {
+    /// Items from the same producer arrive in the order that producer broadcast them, but items from different
+    /// producers may interleave differently for each subscriber.
+    ///
+    /// Acknowledgements, memory budgets and progress tracking go by the order items were broadcast in, so a
+    /// broadcaster using any of them can only be shared with [`Self::Total`].
+    #[default]
+    PerProducerFifo,
+    /// Every subscriber sees every item in the same order. Broadcasts are serialized, so a slow subscriber holds up
+    /// all producers rather than just the one it's currently receiving from.
+    Total,
+}
+
+/// A cloneable handle that several producer tasks can broadcast through.
+///
+/// Subscribe on the [`Broadcaster`] before turning it into a handle. Subscribers see the end of the stream once every
+/// clone of the handle has been dropped.
+pub struct BroadcastHandle<Channel: channel::Channel> {
+    shared: Arc<Shared<Channel>>,
+}
+
+struct Shared<Channel: channel::Channel> {
+    broadcaster: Broadcaster<Channel>,
+    // only held for total ordering
+    broadcast_lock: Option<tokio::sync::Mutex<()>>,
+}
+
+impl<Channel> Clone for BroadcastHandle<Channel>
+where
+    Channel: channel::Channel,
+{
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<Channel> BroadcastHandle<Channel>
+where
+    Channel: channel::Channel,
+{
+    pub(super) fn new(broadcaster: Broadcaster<Channel>, ordering: BroadcastOrdering) -> Self {
+        assert!(
+            ordering == BroadcastOrdering::Total || !broadcaster.tracks_broadcast_order(),
+            "acknowledgements, memory budgets and progress tracking need total ordering",
+        );
+        let broadcast_lock = match ordering {
+            BroadcastOrdering::PerProducerFifo => None,
+            BroadcastOrdering::Total => Some(tokio::sync::Mutex::new(())),
+        };
+        Self {
+            shared: Arc::new(Shared {
+                broadcaster,
+                broadcast_lock,
+            }),
+        }
+    }
+
+    pub fn ordering(&self) -> BroadcastOrdering {
+        match self.shared.broadcast_lock {
+            Some(_) => BroadcastOrdering::Total,
+            None => BroadcastOrdering::PerProducerFifo,
+        }
+    }
+
+    pub async fn broadcast(&self, item: Channel::Item) -> Result<(), BroadcastError> {
+        let _guard = match &self.shared.broadcast_lock {
+            Some(broadcast_lock) => Some(broadcast_lock.lock().await),
+            None => None,
+        };
+        self.shared.broadcaster.broadcast(item).await
+    }
+
+    pub async fn broadcast_from_stream(
+        &self,
+        stream: impl Stream<Item = Channel::Item> + Unpin,
+    ) {
+        let Ok(()) = self
+            .broadcast_from_result_stream(stream.map(Ok::<_, std::convert::Infallible>))
+            .await;
+    }
+
+    /// Reads ahead like [`Broadcaster::broadcast_from_result_stream`]; with total ordering, other producers can
+    /// still broadcast between the items of the stream.
+    pub async fn broadcast_from_result_stream<E>(
+        &self,
+        stream: impl Stream<Item = Result<Channel::Item, E>> + Unpin,
+    ) -> Result<(), E> {
+        self.shared
+            .broadcaster
+            .broadcast_stream_with(stream, |item| self.broadcast(item))
+            .await
+    }
+
+    pub fn get_cancellation_token(&self) -> &CancellationToken {
+        self.shared.broadcaster.get_cancellation_token()
+    }
+
+    pub fn get_pause_token(&self) -> &PauseToken {
+        self.shared.broadcaster.get_pause_token()
+    }
+
+    /// Returns `None` unless the broadcaster is in ack mode.
+    pub fn get_ack_tracker(&self) -> Option<&AckTracker> {
+        self.shared.broadcaster.get_ack_tracker()
+    }
+
+    pub fn get_progress_tracker(&self) -> Option<&ProgressTracker<Channel::Item>> {
+        self.shared.broadcaster.get_progress_tracker()
+    }
+
+    /// The number of live handles, this one included.
+    pub fn producer_count(&self) -> usize {
+        Arc::strong_count(&self.shared)
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::SubscriberId;
+
+/// Caps the bytes in flight across every broadcaster it's attached to.
+///
+/// An item is in flight from the moment it's broadcast until every subscriber has received it, or has been dropped
+/// or evicted. Broadcasting waits while the item would take the total over the budget. An item larger than the
+/// whole budget waits for everything else to land, then goes out on its own.
+///
+/// Clones share the same budget, so one budget can be attached to many drivers at once.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    semaphore: Arc<tokio::sync::Semaphore>,
+    capacity: usize,
+}
+
+impl MemoryBudget {
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            0 < capacity && capacity <= tokio::sync::Semaphore::MAX_PERMITS,
+            "memory budget must be between 1 and {} bytes",
+            tokio::sync::Semaphore::MAX_PERMITS,
+        );
+        Self {
+            semaphore: Arc::new(tokio::sync::Semaphore::new(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The bytes currently in flight, across every broadcaster sharing the budget.
+    pub fn in_use(&self) -> usize {
+        self.capacity - self.available()
+    }
+
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    async fn acquire(&self, bytes: usize) -> tokio::sync::OwnedSemaphorePermit {
+        // permits are acquired as a u32, and anything bigger than the budget could never be acquired
+        let bytes = std::cmp::min(bytes, self.capacity).try_into().unwrap_or(u32::MAX);
+        self.semaphore
+            .clone()
+            .acquire_many_owned(bytes)
+            .await
+            .expect("memory budget semaphore is never closed")
+    }
+}
+
+/// A [`MemoryBudget`] along with how to weigh a broadcaster's items against it.
+///
+/// Any budget converts into one for items that implement [`super::ByteLen`].
+pub struct ItemBudget<Item> {
+    budget: MemoryBudget,
+    byte_len: fn(&Item) -> usize,
+}
+
+impl<Item> ItemBudget<Item> {
+    pub fn new(budget: MemoryBudget, byte_len: fn(&Item) -> usize) -> Self {
+        Self { budget, byte_len }
+    }
+
+    pub fn budget(&self) -> &MemoryBudget {
+        &self.budget
+    }
+
+    pub(super) fn byte_len(&self, item: &Item) -> usize {
+        (self.byte_len)(item)
+    }
+
+    pub(super) async fn acquire(&self, item: &Item) -> tokio::sync::OwnedSemaphorePermit {
+        self.budget.acquire(self.byte_len(item)).await
+    }
+}
+
+impl<Item> From<MemoryBudget> for ItemBudget<Item>
+where
+    Item: super::ByteLen,
+{
+    fn from(budget: MemoryBudget) -> Self {
+        Self::new(budget, Item::byte_len)
+    }
+}
+
+impl<Item> Clone for ItemBudget<Item> {
+    fn clone(&self) -> Self {
+        Self {
+            budget: self.budget.clone(),
+            byte_len: self.byte_len,
+        }
+    }
+}
+
+impl<Item> std::fmt::Debug for ItemBudget<Item> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ItemBudget")
+            .field("budget", &self.budget)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The items one broadcaster has in flight, and how far each of its subscribers has received.
+#[derive(Debug, Default)]
+pub(super) struct InFlight {
+    state: Mutex<InFlightState>,
+}
+
+#[derive(Debug, Default)]
+struct InFlightState {
+    // by sequence number; concurrent producers can push them slightly out of order
+    items: Vec<(u64, tokio::sync::OwnedSemaphorePermit)>,
+    // the sequence number of the next item each subscriber will receive
+    subscribers: HashMap<SubscriberId, u64>,
+}
+
+impl InFlight {
+    /// Holds the permit until every subscriber has received the item.
+    pub(super) fn insert(&self, sequence: u64, permit: tokio::sync::OwnedSemaphorePermit) {
+        let mut state = self.lock();
+        state.items.push((sequence, permit));
+        state.release();
+    }
+
+    pub(super) fn subscribe(
+        self: &Arc<Self>,
+        subscriber_id: SubscriberId,
+        first_sequence: u64,
+    ) -> InFlightSubscriber {
+        self.lock().subscribers.insert(subscriber_id, first_sequence);
+        InFlightSubscriber {
+            in_flight: self.clone(),
+            subscriber_id,
+        }
+    }
+
+    pub(super) fn unsubscribe(&self, subscriber_id: SubscriberId) {
+        let mut state = self.lock();
+        state.subscribers.remove(&subscriber_id);
+        state.release();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, InFlightState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl InFlightState {
+    fn release(&mut self) {
+        match self.subscribers.values().min().copied() {
+            Some(received_by_all) => {
+                self.items.retain(|(sequence, _)| *sequence >= received_by_all)
+            }
+            None => self.items.clear(),
+        }
+    }
+}
+
+/// A subscriber's place in [`InFlight`], which it gives up when dropped.
+#[derive(Debug)]
+pub(super) struct InFlightSubscriber {
+    in_flight: Arc<InFlight>,
+    subscriber_id: SubscriberId,
+}
+
+impl InFlightSubscriber {
+    pub(super) fn received(&self, next_sequence: u64) {
+        let mut state = self.in_flight.lock();
+        if let Some(next) = state.subscribers.get_mut(&self.subscriber_id) {
+            *next = next_sequence;
+            state.release();
+        }
+    }
+}
+
+impl Drop for InFlightSubscriber {
+    fn drop(&mut self) {
+        self.in_flight.unsubscribe(self.subscriber_id);
+    }
+}
@@ -1,26 +1,56 @@
-use futures::{Stream, StreamExt, future::join_all};
+use std::sync::{Arc, atomic::Ordering};
+
+use futures::{Stream, StreamExt, future::join_all, stream::FuturesUnordered};
 
 use crate::channel::{self, sender::Sender};
 
-#[derive(Default, Debug, Clone)]
-pub struct CancellationToken(tokio_util::sync::CancellationToken);
+mod ack;
+mod adaptive;
+mod byte_len;
+mod cancellation;
+mod completion;
+mod deadline;
+mod delivery;
+mod handle;
+mod memory_budget;
+mod pause;
+mod progress;
+mod rate_limit;
+mod subscription;
 
-impl From<tokio_util::sync::CancellationToken> for CancellationToken {
-    fn from(value: tokio_util::sync::CancellationToken) -> Self {
-        Self(value)
-    }
+pub use ack::{AckTracker, Acknowledgement};
+pub use adaptive::AdaptiveBuffer;
+pub use byte_len::{ByteLen, SplitBytes};
+pub use cancellation::{CancellationReason, CancellationToken, Canceller, SubscriberId};
+pub use completion::{Completion, CompletionToken};
+pub use deadline::{DeadlineAction, SendDeadline, SendDeadlineExceeded};
+pub use delivery::Delivery;
+pub use handle::{BroadcastHandle, BroadcastOrdering};
+pub use memory_budget::{ItemBudget, MemoryBudget};
+pub use pause::PauseToken;
+pub use progress::{Progress, ProgressTracker};
+pub use rate_limit::RateLimit;
+pub use subscription::Subscription;
+
+#[derive(Debug)]
+pub enum BroadcastError {
+    Cancelled(CancellationReason),
 }
 
-impl std::ops::Deref for CancellationToken {
-    type Target = tokio_util::sync::CancellationToken;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl std::fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled(reason) => write!(f, "broadcast {reason}"),
+        }
     }
 }
 
-#[derive(Debug)]
-pub enum BroadcastError {
-    ReceiverFailure,
+impl std::error::Error for BroadcastError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Cancelled(reason) => Some(reason),
+        }
+    }
 }
 
 /// A collection of channels.
@@ -32,35 +62,200 @@ pub struct Broadcaster<Channel: channel::Channel> {
     senders: Vec<Channel::Sender>,
     #[builder(default)]
     cancellation_token: CancellationToken,
+    #[builder(default)]
+    pause_token: PauseToken,
+    #[builder(skip = CompletionToken::new(cancellation_token.clone()))]
+    completion_token: CompletionToken,
+    /// Leaves resolving the completion to whoever holds the [`CompletionToken`], instead of resolving it when the
+    /// broadcaster is dropped; subscribers don't see the end of the stream until it's resolved.
+    ///
+    /// For sources that can fail without going through the stream helpers.
+    #[builder(default)]
+    defer_completion: bool,
+    rate_limit: Option<RateLimit<Channel::Item>>,
+    /// Waits while broadcasting would take the bytes in flight over budget; see [`MemoryBudget`].
+    #[builder(into)]
+    memory_budget: Option<ItemBudget<Channel::Item>>,
+    /// Sizes each subscriber's buffer between bounds instead of using `buffer_size` for all of them.
+    adaptive_buffer: Option<AdaptiveBuffer>,
+    #[builder(default)]
+    delivery: Delivery,
+    /// How many items the stream helpers pull ahead of the broadcast in flight; no read-ahead by default.
+    #[builder(default)]
+    prefetch_depth: usize,
+    /// Lets subscribers acknowledge the items they've processed; see [`AckTracker`].
+    #[builder(default)]
+    ack_mode: bool,
+    /// Tracks how many bytes each subscriber has received, for resuming; see [`ProgressTracker`].
+    progress_tracker: Option<ProgressTracker<Channel::Item>>,
+    send_deadline: Option<SendDeadline>,
+    /// Logs a warning (with the `tracing` feature) each time a subscriber has blocked a send for this long.
+    stall_warning: Option<tokio::time::Duration>,
+    // one per sender, in the same order
+    #[builder(skip = (0..senders.len()).map(|index| SubscriberState::new(SubscriberId(index))).collect())]
+    subscribers: Vec<SubscriberState>,
+    #[builder(skip = senders.len())]
+    subscriber_count: usize,
+    #[builder(skip)]
+    sequence: std::sync::atomic::AtomicU64,
+    #[builder(skip)]
+    ack_tracker: AckTracker,
+    #[builder(skip)]
+    in_flight: Arc<memory_budget::InFlight>,
 }
 
 impl<Channel> Broadcaster<Channel>
 where
     Channel: channel::Channel,
 {
-    pub fn subscribe(&mut self) -> Channel::Receiver {
-        let (tx, rx) = self.channel.create_channel(self.buffer_size);
-        self.senders.push(tx.into());
-        rx.into()
+    pub fn subscribe(&mut self) -> Subscription<Channel::Receiver> {
+        let subscriber_id = SubscriberId(self.subscriber_count);
+        self.subscriber_count += 1;
+        let first_sequence = self.sequence.load(Ordering::Acquire);
+        let acknowledged = self
+            .ack_mode
+            .then(|| self.ack_tracker.register(subscriber_id, first_sequence));
+        let (buffer, received) = match &self.adaptive_buffer {
+            Some(adaptive_buffer) => {
+                let (buffer, received) = adaptive::SubscriberBuffer::new(adaptive_buffer.clone(), subscriber_id);
+                (Some(buffer), Some(received))
+            }
+            None => (None, None),
+        };
+        let in_flight = self
+            .memory_budget
+            .is_some()
+            .then(|| self.in_flight.subscribe(subscriber_id, first_sequence));
+        let progress = self
+            .progress_tracker
+            .as_ref()
+            .map(|progress_tracker| progress_tracker.subscribe(subscriber_id, first_sequence));
+        let buffer_size = self
+            .adaptive_buffer
+            .as_ref()
+            .map_or(self.buffer_size, AdaptiveBuffer::max);
+        let (tx, rx) = self.channel.create_channel(buffer_size);
+        let subscriber = SubscriberState {
+            buffer,
+            ..SubscriberState::new(subscriber_id)
+        };
+        let evicted = self
+            .send_deadline
+            .is_some_and(|send_deadline| send_deadline.action == DeadlineAction::Evict)
+            .then(|| subscriber.evicted.clone());
+        self.senders.push(tx);
+        self.subscribers.push(subscriber);
+        Subscription::new(
+            rx,
+            subscriber_id,
+            first_sequence,
+            subscription::Tracking {
+                acknowledged,
+                received,
+                in_flight,
+                progress,
+                evicted,
+            },
+            self.completion_token.clone(),
+            self.defer_completion,
+        )
+    }
+
+    /// Subscribes and returns a cancellation token that cancels on behalf of the new subscriber.
+    pub fn subscribe_with_cancellation_token(
+        &mut self,
+    ) -> (Subscription<Channel::Receiver>, CancellationToken) {
+        let rx = self.subscribe();
+        let cancellation_token = self.cancellation_token.for_subscriber(rx.subscriber_id());
+        (rx, cancellation_token)
     }
 
     pub fn get_cancellation_token(&self) -> &CancellationToken {
         &self.cancellation_token
     }
+
+    pub fn get_completion_token(&self) -> &CompletionToken {
+        &self.completion_token
+    }
+
+    pub fn get_pause_token(&self) -> &PauseToken {
+        &self.pause_token
+    }
+
+    /// Returns `None` unless the broadcaster is in ack mode.
+    pub fn get_ack_tracker(&self) -> Option<&AckTracker> {
+        self.ack_mode.then_some(&self.ack_tracker)
+    }
+
+    pub fn prefetch_depth(&self) -> usize {
+        self.prefetch_depth
+    }
+
+    pub fn get_progress_tracker(&self) -> Option<&ProgressTracker<Channel::Item>> {
+        self.progress_tracker.as_ref()
+    }
+
+    /// The number of items every remaining subscriber has acknowledged, in ack mode.
+    pub fn low_watermark(&self) -> Option<u64> {
+        self.get_ack_tracker()?.low_watermark()
+    }
+
+    /// The number of items each subscriber can have buffered right now; adaptive buffers change this as they go.
+    pub fn buffer_sizes(&self) -> Vec<(SubscriberId, usize)> {
+        self.subscribers
+            .iter()
+            .map(|subscriber| {
+                let buffer_size = subscriber
+                    .buffer
+                    .as_ref()
+                    .map_or(self.buffer_size, adaptive::SubscriberBuffer::capacity);
+                (subscriber.subscriber_id, buffer_size)
+            })
+            .collect()
+    }
+
+    /// Shares the broadcaster between several producers; subscribers see the end of the stream once every handle
+    /// has been dropped.
+    ///
+    /// Panics with [`BroadcastOrdering::PerProducerFifo`] if the broadcaster tracks anything by sequence number: ack
+    /// mode, a memory budget or a progress tracker.
+    pub fn into_handle(self, ordering: BroadcastOrdering) -> BroadcastHandle<Channel> {
+        BroadcastHandle::new(self, ordering)
+    }
+
+    // subscribers number items by the order they arrive in, which only matches the order they were broadcast in
+    // when broadcasts don't overlap
+    fn tracks_broadcast_order(&self) -> bool {
+        self.ack_mode || self.memory_budget.is_some() || self.progress_tracker.is_some()
+    }
 }
 
 impl<Channel> Broadcaster<Channel>
 where
     Channel: channel::Channel,
-    Channel::Item: Clone,
 {
     pub async fn broadcast(&self, item: Channel::Item) -> Result<(), BroadcastError> {
         tokio::select! {
             biased; // no need for random polling; always poll cancellation token first then broadcast
             _ = self.cancellation_token.cancelled() => {
-                Err(BroadcastError::ReceiverFailure)
+                Err(self.cancelled_error())
+            }
+            _ = self.resume_and_broadcast_item(item) => {
+                Ok(())
+            }
+        }
+    }
+
+    /// Waits out a pause, unless the broadcast is cancelled first.
+    ///
+    /// Sources that pull items themselves can call this before pulling the next one, like the stream helpers do.
+    pub async fn wait_until_resumed(&self) -> Result<(), BroadcastError> {
+        tokio::select! {
+            biased;
+            _ = self.cancellation_token.cancelled() => {
+                Err(self.cancelled_error())
             }
-            _ = self.broadcast_item(item) => {
+            _ = self.pause_token.resumed() => {
                 Ok(())
             }
         }
@@ -70,59 +265,336 @@ where
         let send_results = tokio::select! {
             biased; // no need for random polling; always poll cancellation token first then broadcast
             _ = self.cancellation_token.cancelled() => {
-                Err(BroadcastError::ReceiverFailure)
+                Err(self.cancelled_error())
             }
-            send_results = self.broadcast_item(item) => {
+            send_results = self.resume_and_broadcast_item(item) => {
                 Ok(send_results)
             }
         }?;
 
-        // remove senders whose receivers are dead
-        self.senders = self
+        // remove senders whose receivers are dead or that were evicted
+        (self.senders, self.subscribers) = self
             .senders
             .drain(..)
+            .zip(self.subscribers.drain(..))
             .zip(send_results)
-            .filter_map(|(tx, send_result)| match send_result {
-                channel::sender::Result::Success => Some(tx),
+            .filter_map(|(subscriber, send_result)| match send_result {
+                channel::sender::Result::Success => Some(subscriber),
                 channel::sender::Result::Failure => None,
             })
-            .collect();
+            .unzip();
 
         Ok(())
     }
 
     pub async fn broadcast_from_stream(
         &self,
-        mut stream: impl Stream<Item = Channel::Item> + Unpin,
+        stream: impl Stream<Item = Channel::Item> + Unpin,
     ) {
-        while let Some(item) = stream.next().await {
-            if let Err(BroadcastError::ReceiverFailure) = self.broadcast(item).await {
-                break;
-            }
-        }
+        let Ok(()) = self
+            .broadcast_from_result_stream(stream.map(Ok::<_, std::convert::Infallible>))
+            .await;
     }
 
     pub async fn broadcast_from_result_stream<E>(
         &self,
-        mut stream: impl Stream<Item = Result<Channel::Item, E>> + Unpin,
+        stream: impl Stream<Item = Result<Channel::Item, E>> + Unpin,
     ) -> Result<(), E> {
-        while let Some(item) = stream.next().await.transpose()? {
-            if let Err(BroadcastError::ReceiverFailure) = self.broadcast(item).await {
+        self.broadcast_stream_with(stream, |item| self.broadcast(item))
+            .await
+    }
+
+    /// The stream helpers, for `BroadcastHandle` too; `broadcast` sends each item.
+    pub(super) async fn broadcast_stream_with<E, Broadcast>(
+        &self,
+        mut stream: impl Stream<Item = Result<Channel::Item, E>> + Unpin,
+        broadcast: impl Fn(Channel::Item) -> Broadcast,
+    ) -> Result<(), E>
+    where
+        Broadcast: Future<Output = Result<(), BroadcastError>>,
+    {
+        if self.prefetch_depth > 0 {
+            return self.prefetch_and_broadcast(stream, broadcast).await;
+        }
+        // don't pull from the stream while paused
+        while self.wait_until_resumed().await.is_ok()
+            && let Some(item) = stream
+                .next()
+                .await
+                .transpose()
+                .inspect_err(|_| self.completion_token.fail())?
+        {
+            if broadcast(item).await.is_err() {
                 return Ok(());
             }
         }
         Ok(())
     }
 
-    // private helper
-    async fn broadcast_item(&self, item: Channel::Item) -> Vec<channel::sender::Result> {
-        // send messages concurrently
-        join_all(self.senders.iter().map(|tx| {
-            let item = item.clone();
-            tx.send(item)
-        }))
-        .await
+    // private helpers
+    async fn prefetch_and_broadcast<E, Broadcast>(
+        &self,
+        mut stream: impl Stream<Item = Result<Channel::Item, E>> + Unpin,
+        broadcast: impl Fn(Channel::Item) -> Broadcast,
+    ) -> Result<(), E>
+    where
+        Broadcast: Future<Output = Result<(), BroadcastError>>,
+    {
+        let (prefetched_tx, mut prefetched_rx) = tokio::sync::mpsc::channel(self.prefetch_depth);
+        // pulls ahead while the broadcast below is in flight, and stops at the first error
+        let prefetch = async move {
+            while self.wait_until_resumed().await.is_ok() {
+                let item = tokio::select! {
+                    biased;
+                    _ = self.cancellation_token.cancelled() => break,
+                    item = stream.next() => item,
+                };
+                let Some(item) = item else {
+                    break;
+                };
+                let is_err = item.is_err();
+                if prefetched_tx.send(item).await.is_err() || is_err {
+                    break;
+                }
+            }
+        };
+        // owns the receiver, so a cancelled broadcast also stops the prefetching
+        let broadcast = async move {
+            while let Some(item) = prefetched_rx.recv().await {
+                let item = item.inspect_err(|_| self.completion_token.fail())?;
+                if broadcast(item).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        };
+        let ((), broadcast_result) = tokio::join!(prefetch, broadcast);
+        broadcast_result
+    }
+
+    fn cancelled_error(&self) -> BroadcastError {
+        BroadcastError::Cancelled(
+            self.cancellation_token
+                .reason()
+                .unwrap_or_else(|| CancellationReason::new(Canceller::External)),
+        )
+    }
+
+    async fn resume_and_broadcast_item(&self, mut item: Channel::Item) -> Vec<channel::sender::Result> {
+        self.pause_token.resumed().await;
+        // items are split where resumed subscribers left off, so each can skip what it already has
+        let mut send_results: Option<Vec<channel::sender::Result>> = None;
+        while let Some(piece) = self
+            .progress_tracker
+            .as_ref()
+            .and_then(|progress_tracker| progress_tracker.split_at_resumed_offset(&mut item))
+        {
+            let piece_results = self.throttle_and_broadcast_item(piece).await;
+            send_results = Some(match send_results {
+                Some(send_results) => merge_send_results(send_results, piece_results),
+                None => piece_results,
+            });
+        }
+        let item_results = self.throttle_and_broadcast_item(item).await;
+        match send_results {
+            Some(send_results) => merge_send_results(send_results, item_results),
+            None => item_results,
+        }
+    }
+
+    async fn throttle_and_broadcast_item(&self, item: Channel::Item) -> Vec<channel::sender::Result> {
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.acquire(&item).await;
+        }
+        let permit = match &self.memory_budget {
+            Some(memory_budget) => Some(memory_budget.acquire(&item).await),
+            None => None,
+        };
+        self.broadcast_item(item, permit).await
+    }
+
+    async fn broadcast_item(
+        &self,
+        item: Channel::Item,
+        permit: Option<tokio::sync::OwnedSemaphorePermit>,
+    ) -> Vec<channel::sender::Result> {
+        let sequence = self.sequence.fetch_add(1, Ordering::AcqRel);
+        if let Some(permit) = permit {
+            self.in_flight.insert(sequence, permit);
+        }
+        if let Some(progress_tracker) = &self.progress_tracker {
+            progress_tracker.record(sequence, &item);
+        }
+        // adaptive buffers only grow as far as the memory budget could hold items this size
+        let memory = self
+            .memory_budget
+            .as_ref()
+            .map(|memory_budget| (memory_budget.budget(), memory_budget.byte_len(&item)));
+        let item = self.channel.share(item, sequence);
+        let sends = self
+            .senders
+            .iter()
+            .zip(&self.subscribers)
+            .map(|(tx, subscriber)| self.send_to_subscriber(tx, subscriber, item.clone(), memory));
+        match self.delivery {
+            Delivery::Concurrent => join_all(sends).await,
+            Delivery::Unordered => {
+                let mut send_results: Vec<_> = std::iter::repeat_with(|| channel::sender::Result::Failure)
+                    .take(self.senders.len())
+                    .collect();
+                let mut sends: FuturesUnordered<_> = sends
+                    .enumerate()
+                    .map(|(index, send)| async move { (index, send.await) })
+                    .collect();
+                // results come back in completion order, so put them back in subscriber order
+                while let Some((index, send_result)) = sends.next().await {
+                    send_results[index] = send_result;
+                }
+                send_results
+            }
+            Delivery::Sequential => {
+                let mut send_results = Vec::with_capacity(self.senders.len());
+                for send in sends {
+                    send_results.push(send.await);
+                }
+                send_results
+            }
+        }
+    }
+
+    async fn send_to_subscriber(
+        &self,
+        tx: &Channel::Sender,
+        subscriber: &SubscriberState,
+        item: Channel::Shared,
+        memory: Option<(&MemoryBudget, usize)>,
+    ) -> channel::sender::Result {
+        if subscriber.evicted.is_cancelled() {
+            return channel::sender::Result::Failure;
+        }
+        let send = async {
+            if let Some(buffer) = &subscriber.buffer {
+                buffer.reserve(memory).await;
+            }
+            tx.send(item).await
+        };
+        if self.send_deadline.is_none() && self.stall_warning.is_none() {
+            return send.await;
+        }
+
+        let started_at = tokio::time::Instant::now();
+        let deadline = async {
+            match self.send_deadline {
+                Some(send_deadline) => tokio::time::sleep(send_deadline.timeout).await,
+                None => futures::future::pending().await,
+            }
+        };
+        tokio::select! {
+            biased;
+            send_result = send => send_result,
+            _ = deadline => self.miss_send_deadline(subscriber).await,
+            never = self.watch_for_stall(subscriber.subscriber_id, started_at) => match never {},
+        }
+    }
+
+    async fn miss_send_deadline(&self, subscriber: &SubscriberState) -> channel::sender::Result {
+        let Some(send_deadline) = self.send_deadline else {
+            return channel::sender::Result::Failure;
+        };
+        match send_deadline.action {
+            DeadlineAction::Evict => {
+                // ends the subscriber's stream once it has taken what was already sent to it
+                subscriber.evicted.cancel();
+                // it won't receive anything else, so stop holding memory budget for it
+                self.in_flight.unsubscribe(subscriber.subscriber_id);
+                // nor let it hold back the low watermark
+                self.ack_tracker.detach(subscriber.subscriber_id);
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    subscriber_id = subscriber.subscriber_id.index(),
+                    timeout_ms = send_deadline.timeout.as_millis(),
+                    "evicted subscriber that missed its send deadline",
+                );
+                channel::sender::Result::Failure
+            }
+            DeadlineAction::Cancel => {
+                self.cancellation_token
+                    .cancel_with_reason(CancellationReason::with_error(
+                        Canceller::Broadcaster,
+                        SendDeadlineExceeded {
+                            subscriber_id: subscriber.subscriber_id,
+                            timeout: send_deadline.timeout,
+                        },
+                    ));
+                // the cancellation branch of the broadcast takes over from here
+                futures::future::pending().await
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn watch_for_stall(
+        &self,
+        subscriber_id: SubscriberId,
+        started_at: tokio::time::Instant,
+    ) -> std::convert::Infallible {
+        let Some(stall_warning) = self.stall_warning else {
+            return futures::future::pending().await;
+        };
+        let mut interval = tokio::time::interval_at(started_at + stall_warning, stall_warning);
+        loop {
+            interval.tick().await;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                subscriber_id = subscriber_id.index(),
+                blocked_ms = started_at.elapsed().as_millis(),
+                "subscriber is blocking the broadcast",
+            );
+        }
+    }
+}
+
+impl<Channel> Drop for Broadcaster<Channel>
+where
+    Channel: channel::Channel,
+{
+    fn drop(&mut self) {
+        // resolve before the senders are dropped, so subscribers know how it ended once their channel closes
+        if !self.defer_completion {
+            self.completion_token.finish();
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SubscriberState {
+    subscriber_id: SubscriberId,
+    // shared with the subscription, so it can tell its stream was cut short
+    evicted: tokio_util::sync::CancellationToken,
+    buffer: Option<adaptive::SubscriberBuffer>,
+}
+
+impl SubscriberState {
+    fn new(subscriber_id: SubscriberId) -> Self {
+        Self {
+            subscriber_id,
+            evicted: tokio_util::sync::CancellationToken::new(),
+            buffer: None,
+        }
+    }
+}
+
+// a subscriber fails an item if it failed any piece of it
+fn merge_send_results(
+    mut send_results: Vec<channel::sender::Result>,
+    piece_results: Vec<channel::sender::Result>,
+) -> Vec<channel::sender::Result> {
+    for (send_result, piece_result) in send_results.iter_mut().zip(piece_results) {
+        if let channel::sender::Result::Failure = piece_result {
+            *send_result = channel::sender::Result::Failure;
+        }
     }
+    send_results
 }
 
 #[cfg(test)]
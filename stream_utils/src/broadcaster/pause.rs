@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+/// Pauses and resumes a [`super::Broadcaster`] without cancelling it.
+///
+/// Clones share the same state. While paused, broadcasts wait before sending and the stream helpers stop pulling
+/// from their stream; cancelling still takes priority.
+#[derive(Debug, Clone)]
+pub struct PauseToken {
+    paused: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl Default for PauseToken {
+    fn default() -> Self {
+        Self {
+            paused: Arc::new(tokio::sync::watch::Sender::new(false)),
+        }
+    }
+}
+
+impl PauseToken {
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Returns immediately unless paused.
+    pub async fn resumed(&self) {
+        let mut paused = self.paused.subscribe();
+        // the sender lives as long as `self`, so this can't fail
+        let _ = paused.wait_for(|paused| !paused).await;
+    }
+}
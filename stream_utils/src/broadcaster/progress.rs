@@ -0,0 +1,239 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use super::SubscriberId;
+
+/// How far into the source a subscriber has received, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub subscriber_id: SubscriberId,
+    /// The byte offset right after the last item the subscriber received, counting from the start of the source
+    /// rather than the start of this broadcast.
+    pub offset: u64,
+}
+
+/// Tracks how many bytes of the source each subscriber has received, so a failed broadcast can be resumed from
+/// there; see [`crate::fanout::source::FanoutSource::resume_from`].
+///
+/// Offsets follow the order items are broadcast in, so a broadcaster tracking progress can only be shared between
+/// producers with [`super::BroadcastOrdering::Total`]. Clones share the same state, like [`super::AckTracker`].
+pub struct ProgressTracker<Item> {
+    state: Arc<Mutex<ProgressState>>,
+    byte_len: fn(&Item) -> usize,
+    split_to: Option<fn(&mut Item, usize) -> Item>,
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    start_offset: u64,
+    // the offset right after the last item broadcast
+    end_offset: u64,
+    // the sequence number of the first item in `item_ends`
+    first_sequence: u64,
+    // the offset right after each item that not every subscriber has received yet
+    item_ends: VecDeque<u64>,
+    subscribers: Vec<SubscriberProgress>,
+    // how far each subscriber got before the broadcast was resumed
+    resumed_offsets: Vec<Progress>,
+}
+
+#[derive(Debug)]
+struct SubscriberProgress {
+    subscriber_id: SubscriberId,
+    offset: u64,
+    // what it already received before the broadcast was resumed
+    resumed_offset: u64,
+    // `None` once the subscription is dropped, so it no longer holds on to item offsets
+    next_sequence: Option<u64>,
+}
+
+impl<Item> ProgressTracker<Item> {
+    /// `start_offset` is where in the source the broadcast starts, e.g. the offset it resumed from.
+    pub fn new(start_offset: u64, byte_len: fn(&Item) -> usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ProgressState {
+                start_offset,
+                end_offset: start_offset,
+                first_sequence: 0,
+                item_ends: VecDeque::new(),
+                subscribers: Vec::new(),
+                resumed_offsets: Vec::new(),
+            })),
+            byte_len,
+            split_to: None,
+        }
+    }
+
+    /// Resumes subscribers that got further than the start offset, so none receives a byte twice.
+    ///
+    /// `resumed_offsets` are how far each subscriber got, e.g. [`crate::fanout::UsedStreamFanout::progress`], and
+    /// are matched to the new subscribers by [`SubscriberId`]; a subscriber without one starts at the start offset.
+    /// Items are split with `split_to` where one of them left off, e.g. with [`super::SplitBytes::split_to`], and
+    /// each subscription skips the items that end before its own offset.
+    pub fn with_resumed_offsets(
+        self,
+        resumed_offsets: Vec<Progress>,
+        split_to: fn(&mut Item, usize) -> Item,
+    ) -> Self {
+        self.lock().resumed_offsets = resumed_offsets;
+        Self {
+            split_to: Some(split_to),
+            ..self
+        }
+    }
+
+    pub fn progress(&self) -> Vec<Progress> {
+        self.lock()
+            .subscribers
+            .iter()
+            .map(|subscriber| Progress {
+                subscriber_id: subscriber.subscriber_id,
+                offset: subscriber.offset,
+            })
+            .collect()
+    }
+
+    /// The offset every subscriber has received up to, or `None` without subscribers.
+    pub fn low_watermark(&self) -> Option<u64> {
+        self.lock()
+            .subscribers
+            .iter()
+            .map(|subscriber| subscriber.offset)
+            .min()
+    }
+
+    /// The bytes broadcast so far, not counting the start offset.
+    pub fn bytes_broadcast(&self) -> u64 {
+        let state = self.lock();
+        state.end_offset - state.start_offset
+    }
+
+    pub(super) fn record(&self, sequence: u64, item: &Item) {
+        let byte_len = (self.byte_len)(item) as u64;
+        let mut state = self.lock();
+        state.end_offset += byte_len;
+        // with no subscriber left to need it, only the end offset matters
+        if sequence >= state.first_sequence && state.live_subscribers().next().is_some() {
+            let end_offset = state.end_offset;
+            state.item_ends.push_back(end_offset);
+        } else {
+            state.first_sequence = sequence + 1;
+        }
+    }
+
+    /// Splits off the start of `item` if a resumed subscriber left off partway through it.
+    pub(super) fn split_at_resumed_offset(&self, item: &mut Item) -> Option<Item> {
+        let split_to = self.split_to?;
+        let state = self.lock();
+        let item_start = state.end_offset;
+        let item_end = item_start + (self.byte_len)(item) as u64;
+        let split_at = state
+            .resumed_offsets
+            .iter()
+            .map(|resumed| resumed.offset)
+            .filter(|offset| (item_start + 1..item_end).contains(offset))
+            .min()?;
+        Some(split_to(item, (split_at - item_start) as usize))
+    }
+
+    pub(super) fn subscribe(&self, subscriber_id: SubscriberId, first_sequence: u64) -> ProgressSubscriber {
+        let mut state = self.lock();
+        if state.live_subscribers().next().is_none() {
+            state.first_sequence = first_sequence;
+        }
+        let resumed_offset = state
+            .resumed_offsets
+            .iter()
+            .find(|resumed| resumed.subscriber_id == subscriber_id)
+            .map_or(0, |resumed| resumed.offset);
+        let offset = std::cmp::max(state.end_offset, resumed_offset);
+        state.subscribers.push(SubscriberProgress {
+            subscriber_id,
+            offset,
+            resumed_offset,
+            next_sequence: Some(first_sequence),
+        });
+        ProgressSubscriber {
+            state: self.state.clone(),
+            index: state.subscribers.len() - 1,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProgressState> {
+        lock(&self.state)
+    }
+}
+
+impl<Item> Clone for ProgressTracker<Item> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            byte_len: self.byte_len,
+            split_to: self.split_to,
+        }
+    }
+}
+
+impl<Item> std::fmt::Debug for ProgressTracker<Item> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressTracker")
+            .field("progress", &self.progress())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProgressState {
+    fn live_subscribers(&self) -> impl Iterator<Item = u64> + '_ {
+        self.subscribers
+            .iter()
+            .filter_map(|subscriber| subscriber.next_sequence)
+    }
+
+    // forgets the offsets of items every live subscriber has received
+    fn release(&mut self) {
+        let received_by_all = self.live_subscribers().min().unwrap_or(u64::MAX);
+        while self.first_sequence < received_by_all && self.item_ends.pop_front().is_some() {
+            self.first_sequence += 1;
+        }
+    }
+}
+
+fn lock(state: &Mutex<ProgressState>) -> std::sync::MutexGuard<'_, ProgressState> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A subscriber's place in a [`ProgressTracker`], which stops holding on to item offsets when dropped.
+#[derive(Debug)]
+pub(super) struct ProgressSubscriber {
+    state: Arc<Mutex<ProgressState>>,
+    index: usize,
+}
+
+impl ProgressSubscriber {
+    /// Returns whether the subscriber already had the item before the broadcast was resumed.
+    pub(super) fn received(&self, next_sequence: u64) -> bool {
+        let mut state = lock(&self.state);
+        let Some(item_end) = next_sequence
+            .checked_sub(state.first_sequence + 1)
+            .and_then(|index| state.item_ends.get(index as usize).copied())
+        else {
+            return false;
+        };
+        let subscriber = &mut state.subscribers[self.index];
+        subscriber.offset = std::cmp::max(item_end, subscriber.resumed_offset);
+        subscriber.next_sequence = Some(next_sequence);
+        let already_received = item_end <= subscriber.resumed_offset;
+        state.release();
+        already_received
+    }
+}
+
+impl Drop for ProgressSubscriber {
+    fn drop(&mut self) {
+        let mut state = lock(&self.state);
+        state.subscribers[self.index].next_sequence = None;
+        state.release();
+    }
+}
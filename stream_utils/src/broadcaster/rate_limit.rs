@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+
+use tokio::time::{Duration, Instant};
+
+/// Caps how fast a [`super::Broadcaster`] sends items, using one token bucket per limit.
+///
+/// Each bucket starts full, so up to `burst` items (or bytes) go out immediately before the rate kicks in.
+/// An item larger than the burst is still sent, but the following items wait until the bucket has paid it off.
+pub struct RateLimit<Item> {
+    items: Option<TokenBucket>,
+    bytes: Option<ByteTokenBucket<Item>>,
+}
+
+struct ByteTokenBucket<Item> {
+    bucket: TokenBucket,
+    byte_len: fn(&Item) -> usize,
+}
+
+impl<Item> RateLimit<Item> {
+    /// A rate limit that doesn't limit anything until limits are added.
+    pub fn new() -> Self {
+        Self {
+            items: None,
+            bytes: None,
+        }
+    }
+
+    pub fn items_per_second(self, rate: u64, burst: u64) -> Self {
+        Self {
+            items: Some(TokenBucket::new(rate, burst)),
+            ..self
+        }
+    }
+
+    /// Waits until every bucket has room for `item`.
+    pub(super) async fn acquire(&self, item: &Item) {
+        let wait = std::cmp::max(
+            self.items.as_ref().map(|bucket| bucket.reserve(1)),
+            self.bytes
+                .as_ref()
+                .map(|bytes| bytes.bucket.reserve((bytes.byte_len)(item) as u64)),
+        );
+        if let Some(wait) = wait.filter(|wait| !wait.is_zero()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl<Item> RateLimit<Item>
+where
+    Item: super::ByteLen,
+{
+    pub fn bytes_per_second(self, rate: u64, burst: u64) -> Self {
+        Self {
+            bytes: Some(ByteTokenBucket {
+                bucket: TokenBucket::new(rate, burst),
+                byte_len: Item::byte_len,
+            }),
+            ..self
+        }
+    }
+}
+
+impl<Item> Default for RateLimit<Item> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// cloning starts over with full buckets
+impl<Item> Clone for RateLimit<Item> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.as_ref().map(TokenBucket::reset),
+            bytes: self.bytes.as_ref().map(|bytes| ByteTokenBucket {
+                bucket: bytes.bucket.reset(),
+                byte_len: bytes.byte_len,
+            }),
+        }
+    }
+}
+
+impl<Item> std::fmt::Debug for RateLimit<Item> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("items", &self.items)
+            .field("bytes", &self.bytes.as_ref().map(|bytes| &bytes.bucket))
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: u64,
+    burst: u64,
+    state: Mutex<TokenBucketState>,
+}
+
+#[derive(Debug)]
+struct TokenBucketState {
+    // negative when an item larger than the available tokens was let through
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, burst: u64) -> Self {
+        assert!(rate > 0, "rate limit must allow at least one token per second");
+        Self {
+            rate,
+            burst,
+            state: Mutex::new(TokenBucketState {
+                tokens: burst as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn reset(&self) -> Self {
+        Self::new(self.rate, self.burst)
+    }
+
+    /// Takes `tokens` from the bucket and returns how long to wait until they would have been available.
+    fn reserve(&self, tokens: u64) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(state.refilled_at).as_secs_f64() * self.rate as f64;
+        state.tokens = (state.tokens + refill).min(self.burst as f64);
+        state.refilled_at = now;
+        state.tokens -= tokens as f64;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate as f64)
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::channel::receiver::Receiver;
+
+use super::{
+    Completion, CompletionToken, SubscriberId, ack::AckSubscriber, adaptive::ReceivedTracker,
+    memory_budget::InFlightSubscriber, progress::ProgressSubscriber,
+};
+
+/// The receiving end of a [`super::Broadcaster`] subscription.
+///
+/// Counts the items it receives so they can be acknowledged by sequence number.
+#[derive(Debug)]
+pub struct Subscription<Rx> {
+    rx: Rx,
+    subscriber_id: SubscriberId,
+    first_sequence: u64,
+    next_sequence: u64,
+    last_sequence: Option<u64>,
+    tracking: Tracking,
+    completion_token: CompletionToken,
+    // the end of the stream has to wait for a deferred completion to be resolved
+    await_completion: bool,
+}
+
+/// What the broadcaster keeps track of through a subscription, depending on how it's configured.
+#[derive(Debug)]
+pub(super) struct Tracking {
+    // detaches from the ack tracker once the subscriber is gone
+    pub(super) acknowledged: Option<AckSubscriber>,
+    // lets an adaptive buffer see how far behind the subscriber is
+    pub(super) received: Option<ReceivedTracker>,
+    // releases memory budget as items are received
+    pub(super) in_flight: Option<InFlightSubscriber>,
+    // counts the bytes received, for resuming
+    pub(super) progress: Option<ProgressSubscriber>,
+    // cancelled once the subscriber is evicted for missing its send deadline
+    pub(super) evicted: Option<tokio_util::sync::CancellationToken>,
+}
+
+impl<Rx> Subscription<Rx> {
+    pub(super) fn new(
+        rx: Rx,
+        subscriber_id: SubscriberId,
+        first_sequence: u64,
+        tracking: Tracking,
+        completion_token: CompletionToken,
+        await_completion: bool,
+    ) -> Self {
+        Self {
+            rx,
+            subscriber_id,
+            first_sequence,
+            next_sequence: first_sequence,
+            last_sequence: None,
+            tracking,
+            completion_token,
+            await_completion,
+        }
+    }
+
+    pub fn subscriber_id(&self) -> SubscriberId {
+        self.subscriber_id
+    }
+
+    /// Nothing is tracked through the receiver once it's taken out: it can't acknowledge items, and an adaptive
+    /// buffer stops limiting what's sent to it to the channel's own bound.
+    pub fn into_inner(self) -> Rx {
+        self.rx
+    }
+
+    fn is_evicted(&self) -> bool {
+        self.tracking
+            .evicted
+            .as_ref()
+            .is_some_and(tokio_util::sync::CancellationToken::is_cancelled)
+    }
+}
+
+impl<Rx> Subscription<Rx>
+where
+    Rx: Receiver,
+{
+    pub async fn recv(&mut self) -> Option<Rx::Item> {
+        loop {
+            let item = match &self.tracking.evicted {
+                // whatever was sent before the eviction is still received first
+                Some(evicted) => tokio::select! {
+                    biased;
+                    item = self.rx.recv() => item,
+                    () = evicted.cancelled() => None,
+                },
+                None => self.rx.recv().await,
+            };
+            let Some(item) = item else {
+                if self.await_completion && !self.is_evicted() {
+                    self.completion_token.resolved().await;
+                }
+                return None;
+            };
+            // a resumed subscriber skips what it received before the broadcast was resumed
+            if !self.record_received() {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Returns whether the subscriber already had the item it just received.
+    fn record_received(&mut self) -> bool {
+        // channels that skip items, like `LatestChannel`, say which item this is
+        let sequence = self.rx.sequence().unwrap_or(self.next_sequence);
+        self.last_sequence = Some(sequence);
+        self.next_sequence = sequence + 1;
+        if let Some(received) = &self.tracking.received {
+            received.record(self.next_sequence - self.first_sequence);
+        }
+        if let Some(in_flight) = &self.tracking.in_flight {
+            in_flight.received(self.next_sequence);
+        }
+        self.tracking
+            .progress
+            .as_ref()
+            .is_some_and(|progress| progress.received(self.next_sequence))
+    }
+}
+
+impl<Rx> Receiver for Subscription<Rx>
+where
+    Rx: Receiver,
+{
+    type Item = Rx::Item;
+    async fn recv(&mut self) -> Option<Self::Item> {
+        Subscription::recv(self).await
+    }
+    fn sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+    fn completion(&self) -> Option<Completion> {
+        if self.is_evicted() {
+            return Some(Completion::Evicted);
+        }
+        self.completion_token.get()
+    }
+    fn ack(&self, sequence: u64) {
+        if let Some(acknowledged) = &self.tracking.acknowledged {
+            // acknowledgements only move forward, and never past what was received
+            acknowledged.acknowledge(std::cmp::min(sequence + 1, self.next_sequence));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use futures::StreamExt;
 
 use super::Broadcaster;
 
@@ -118,3 +119,1136 @@ async fn test_broadcaster_abort_if_cancellation_token() {
     };
     tokio::join!(broadcast_future, rx1_future, rx2_future);
 }
+
+#[tokio::test]
+async fn test_broadcaster_surfaces_cancellation_reason() {
+    #[derive(Debug)]
+    struct TooBig;
+    impl std::fmt::Display for TooBig {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("too big")
+        }
+    }
+    impl std::error::Error for TooBig {}
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let _rx1 = broadcaster.subscribe();
+    let (_rx2, cancellation_token) = broadcaster.subscribe_with_cancellation_token();
+    cancellation_token.cancel_with_error(TooBig);
+    // a second cancellation doesn't overwrite the first reason
+    broadcaster.get_cancellation_token().cancel();
+
+    let Err(super::BroadcastError::Cancelled(reason)) = broadcaster.broadcast(0).await else {
+        panic!("broadcast should fail once cancelled");
+    };
+    assert_eq!(
+        reason.canceller(),
+        super::Canceller::Subscriber(super::SubscriberId(1))
+    );
+    assert_eq!(reason.error().unwrap().to_string(), "too big");
+    assert_eq!(reason.to_string(), "cancelled by subscriber 1: too big");
+}
+
+#[tokio::test]
+async fn test_broadcaster_external_cancellation_reason() {
+    let broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .cancellation_token(tokio_util::sync::CancellationToken::new().into())
+        .build();
+    broadcaster.get_cancellation_token().cancel();
+    let Err(super::BroadcastError::Cancelled(reason)) = broadcaster.broadcast(0).await else {
+        panic!("broadcast should fail once cancelled");
+    };
+    assert_eq!(reason.canceller(), super::Canceller::External);
+    assert!(reason.error().is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_rate_limit_allows_burst_then_throttles() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .rate_limit(super::RateLimit::new().items_per_second(10, 2))
+        .build();
+    let _rx = broadcaster.subscribe();
+    let start = tokio::time::Instant::now();
+    for message in 0..2 {
+        broadcaster.broadcast(message).await.unwrap();
+    }
+    assert_eq!(start.elapsed(), tokio::time::Duration::ZERO);
+    for message in 2..7 {
+        broadcaster.broadcast(message).await.unwrap();
+    }
+    // 5 items past the burst at 10 items per second
+    assert!(
+        start.elapsed() >= tokio::time::Duration::from_millis(500),
+        "elapsed: {:?}",
+        start.elapsed()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_rate_limit_bytes_per_second() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .rate_limit(super::RateLimit::new().bytes_per_second(100, 100))
+        .build();
+    let _rx = broadcaster.subscribe();
+    let start = tokio::time::Instant::now();
+    for _ in 0..3 {
+        broadcaster
+            .broadcast(bytes::Bytes::from_static(&[0u8; 100]))
+            .await
+            .unwrap();
+    }
+    assert!(
+        start.elapsed() >= tokio::time::Duration::from_secs(2),
+        "elapsed: {:?}",
+        start.elapsed()
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_rate_limit_respects_cancellation() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .rate_limit(super::RateLimit::new().items_per_second(1, 1))
+        .build();
+    let _rx = broadcaster.subscribe();
+    let cancellation_token = broadcaster.get_cancellation_token().clone();
+    broadcaster.broadcast(0).await.unwrap();
+    let start = tokio::time::Instant::now();
+    let (broadcast_result, _) = tokio::join!(broadcaster.broadcast(1), async {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        cancellation_token.cancel();
+    });
+    assert!(broadcast_result.is_err());
+    assert!(
+        start.elapsed() < tokio::time::Duration::from_secs(1),
+        "elapsed: {:?}",
+        start.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn test_broadcaster_arc_channel_shares_non_clone_items() {
+    use crate::channel::ArcItemExt;
+
+    #[derive(Debug, PartialEq)]
+    struct Record(i32);
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(crate::channel::ArcChannel::new(
+            tokio::sync::mpsc::channel::<std::sync::Arc<Record>>,
+        ))
+        .build();
+    let mut rx1 = broadcaster.subscribe();
+    let mut rx2 = broadcaster.subscribe();
+    broadcaster.broadcast(Record(1)).await.unwrap();
+    drop(broadcaster);
+
+    let record1 = rx1.recv().await.unwrap();
+    let record2 = rx2.recv().await.unwrap();
+    // wrapped once, not once per subscriber
+    assert!(std::sync::Arc::ptr_eq(&record1, &record2));
+    let record1 = record1.try_into_owned().unwrap_err();
+    drop(record2);
+    assert_eq!(record1.try_into_owned().unwrap(), Record(1));
+    assert!(rx1.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn test_broadcaster_ack_mode_tracks_low_watermark() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(5)
+        .channel(TOKIO_CHANNEL)
+        .ack_mode(true)
+        .build();
+    let mut rx1 = broadcaster.subscribe();
+    let mut rx2 = broadcaster.subscribe();
+    for message in 0..5 {
+        broadcaster.broadcast(message).await.unwrap();
+    }
+    assert_eq!(broadcaster.low_watermark(), Some(0));
+
+    for _ in 0..4 {
+        rx1.recv().await.unwrap();
+    }
+    rx1.ack(rx1.sequence().unwrap());
+    rx2.recv().await.unwrap();
+    rx2.recv().await.unwrap();
+    assert_eq!(rx2.sequence(), Some(1));
+    rx2.ack(1);
+    // acknowledging something not yet received only counts what was received
+    rx2.ack(10);
+    assert_eq!(broadcaster.low_watermark(), Some(2));
+
+    let acknowledgements = broadcaster.get_ack_tracker().unwrap().acknowledgements();
+    assert_eq!(
+        acknowledgements
+            .iter()
+            .map(|acknowledgement| acknowledgement.acknowledged)
+            .collect_vec(),
+        vec![4, 2]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_low_watermark_ignores_departed_subscribers() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .ack_mode(true)
+        .send_deadline(super::SendDeadline::evict(tokio::time::Duration::from_millis(100)))
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let dropped_rx = broadcaster.subscribe();
+    let unwrapped_rx = broadcaster.subscribe();
+    let _stalled_rx = broadcaster.subscribe();
+    drop(dropped_rx);
+    let _unwrapped_rx = unwrapped_rx.into_inner();
+
+    tokio::join!(
+        async {
+            for message in 0..3 {
+                broadcaster.broadcast(message).await.unwrap();
+            }
+        },
+        async {
+            while let Some(message) = rx.recv().await {
+                rx.ack(rx.sequence().unwrap());
+                if message == 2 {
+                    break;
+                }
+            }
+        },
+    );
+
+    // the stalled subscriber was evicted, so it no longer holds the low watermark at zero
+    assert_eq!(broadcaster.low_watermark(), Some(3));
+    // but every subscriber's last acknowledgement is still reported
+    assert_eq!(
+        broadcaster
+            .get_ack_tracker()
+            .unwrap()
+            .acknowledgements()
+            .iter()
+            .map(|acknowledgement| acknowledgement.acknowledged)
+            .collect_vec(),
+        vec![3, 0, 0, 0]
+    );
+    drop(rx);
+    assert_eq!(broadcaster.low_watermark(), None);
+}
+
+#[tokio::test]
+async fn test_broadcaster_without_ack_mode_ignores_acks() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    broadcaster.broadcast(0).await.unwrap();
+    rx.recv().await.unwrap();
+    rx.ack(0);
+    assert!(broadcaster.get_ack_tracker().is_none());
+    assert_eq!(broadcaster.low_watermark(), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_send_deadline_evicts_stalled_subscriber() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .send_deadline(super::SendDeadline::evict(tokio::time::Duration::from_millis(100)))
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let _stalled_rx = broadcaster.subscribe();
+    let (_, received) = tokio::join!(
+        async {
+            for message in 0..5 {
+                broadcaster.broadcast_and_prune(message).await.unwrap();
+            }
+            drop(broadcaster);
+        },
+        async {
+            let mut received = Vec::new();
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+            }
+            received
+        },
+    );
+    assert_eq!(received, vec![0, 1, 2, 3, 4]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_evicted_subscriber_sees_truncated_stream() {
+    use crate::channel::receiver::Receiver;
+
+    // deferred like under a fanout driver, so the broadcast's own completion isn't what ends the stream
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .send_deadline(super::SendDeadline::evict(tokio::time::Duration::from_millis(100)))
+        .defer_completion(true)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let mut stalled_rx = broadcaster.subscribe();
+    tokio::join!(
+        async {
+            for message in 0..5 {
+                broadcaster.broadcast(message).await.unwrap();
+            }
+        },
+        async { while rx.recv().await.is_some_and(|message| message < 4) {} },
+    );
+
+    // the item that was already sent still arrives, then the stream ends without waiting for the broadcast
+    assert_eq!(stalled_rx.recv().await, Some(0));
+    assert_eq!(stalled_rx.recv().await, None);
+    assert!(matches!(stalled_rx.completion(), Some(super::Completion::Evicted)));
+    broadcaster.get_completion_token().finish();
+    assert!(matches!(stalled_rx.completion(), Some(super::Completion::Evicted)));
+    assert!(rx.completion().is_some_and(|completion| completion.is_finished()));
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_stall_warning_repeats_while_blocked() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    struct WarningCounter(Arc<AtomicUsize>);
+
+    impl tracing::Subscriber for WarningCounter {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+        fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+        fn event(&self, event: &tracing::Event<'_>) {
+            if *event.metadata().level() == tracing::Level::WARN {
+                self.0.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+        fn enter(&self, _span: &tracing::span::Id) {}
+        fn exit(&self, _span: &tracing::span::Id) {}
+    }
+
+    let warnings = Arc::new(AtomicUsize::new(0));
+    let _guard = tracing::subscriber::set_default(WarningCounter(warnings.clone()));
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .stall_warning(tokio::time::Duration::from_millis(100))
+        .build();
+    let mut rx = broadcaster.subscribe();
+    broadcaster.broadcast(0).await.unwrap();
+    assert_eq!(warnings.load(Ordering::Acquire), 0);
+
+    // blocked for 350ms, so warned at 100, 200 and 300, and the item still gets through
+    tokio::join!(
+        async { broadcaster.broadcast(1).await.unwrap() },
+        async {
+            tokio::time::sleep(tokio::time::Duration::from_millis(350)).await;
+            assert_eq!(rx.recv().await, Some(0));
+        },
+    );
+    assert_eq!(rx.recv().await, Some(1));
+    assert_eq!(warnings.load(Ordering::Acquire), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_send_deadline_cancels_broadcast() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .send_deadline(super::SendDeadline::cancel(tokio::time::Duration::from_millis(100)))
+        .build();
+    let _stalled_rx = broadcaster.subscribe();
+    broadcaster.broadcast(0).await.unwrap();
+    let Err(super::BroadcastError::Cancelled(reason)) = broadcaster.broadcast(1).await else {
+        panic!("broadcast should fail once the deadline is missed");
+    };
+    assert_eq!(reason.canceller(), super::Canceller::Broadcaster);
+    let error = reason
+        .error()
+        .and_then(|error| error.downcast_ref::<super::SendDeadlineExceeded>())
+        .expect("reason should carry the missed deadline");
+    assert_eq!(error.subscriber_id.index(), 0);
+}
+
+#[tokio::test]
+async fn test_broadcast_handle_per_producer_fifo_and_end_of_stream() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(4)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rxs = (0..2).map(|_| broadcaster.subscribe()).collect_vec();
+    let handle = broadcaster.into_handle(super::BroadcastOrdering::PerProducerFifo);
+    let producers = (0..3)
+        .map(|producer| {
+            let handle = handle.clone();
+            async move {
+                for message in 0..10 {
+                    handle.broadcast(producer * 100 + message).await.unwrap();
+                }
+            }
+        })
+        .collect_vec();
+    // subscribers only see the end of the stream once the original handle is gone too
+    drop(handle);
+    let (_, received) = tokio::join!(
+        futures::future::join_all(producers),
+        futures::future::join_all(rxs.iter_mut().map(|rx| async {
+            let mut received = Vec::new();
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+            }
+            received
+        })),
+    );
+    for received in received {
+        assert_eq!(received.len(), 30);
+        for producer in 0..3 {
+            let from_producer = received
+                .iter()
+                .filter(|message| **message / 100 == producer)
+                .copied()
+                .collect_vec();
+            assert_eq!(from_producer, (0..10).map(|message| producer * 100 + message).collect_vec());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_broadcast_handle_total_ordering() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rxs = (0..3).map(|_| broadcaster.subscribe()).collect_vec();
+    let handle = broadcaster.into_handle(super::BroadcastOrdering::Total);
+    let producers = (0..3)
+        .map(|producer| {
+            let handle = handle.clone();
+            async move {
+                for message in 0..10 {
+                    handle.broadcast(producer * 100 + message).await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+            }
+        })
+        .collect_vec();
+    drop(handle);
+    let (_, received) = tokio::join!(
+        futures::future::join_all(producers),
+        futures::future::join_all(rxs.iter_mut().map(|rx| async {
+            let mut received = Vec::new();
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+                tokio::task::yield_now().await;
+            }
+            received
+        })),
+    );
+    assert_eq!(received[0].len(), 30);
+    assert!(received.iter().all_equal());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_broadcast_handle_total_ordering_keeps_tracking_consistent() {
+    use crate::channel::receiver::Receiver;
+
+    let memory_budget = super::MemoryBudget::new(64);
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(2)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .ack_mode(true)
+        .memory_budget(memory_budget.clone())
+        .progress_tracker(super::ProgressTracker::new(0, bytes::Bytes::len))
+        .build();
+    let rxs = (0..3).map(|_| broadcaster.subscribe()).collect_vec();
+    let handle = broadcaster.into_handle(super::BroadcastOrdering::Total);
+    let ack_tracker = handle.get_ack_tracker().unwrap().clone();
+    let progress_tracker = handle.get_progress_tracker().unwrap().clone();
+    let consumers = rxs
+        .into_iter()
+        .map(|mut rx| {
+            tokio::spawn(async move {
+                let mut received = 0;
+                while let Some(item) = rx.recv().await {
+                    received += item.len() as u64;
+                    rx.ack(rx.sequence().unwrap());
+                }
+                // kept alive until the tracking has been checked
+                (rx, received)
+            })
+        })
+        .collect_vec();
+    // every producer sends items of its own size, so mixing up their sequence numbers would show in the offsets
+    let producers = (1..=4)
+        .map(|producer| {
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                for _ in 0..25 {
+                    handle.broadcast(bytes::Bytes::from(vec![0u8; producer])).await.unwrap();
+                }
+            })
+        })
+        .collect_vec();
+    drop(handle);
+    futures::future::try_join_all(producers).await.unwrap();
+    let consumed = futures::future::try_join_all(consumers).await.unwrap();
+
+    let total = 25 * (1 + 2 + 3 + 4);
+    assert!(consumed.iter().all(|(_, received)| *received == total));
+    assert_eq!(ack_tracker.low_watermark(), Some(100));
+    assert_eq!(progress_tracker.low_watermark(), Some(total));
+    assert_eq!(memory_budget.in_use(), 0);
+}
+
+#[tokio::test]
+#[should_panic(expected = "need total ordering")]
+async fn test_broadcast_handle_per_producer_fifo_rejects_ack_mode() {
+    let broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .ack_mode(true)
+        .build();
+    broadcaster.into_handle(super::BroadcastOrdering::PerProducerFifo);
+}
+
+#[tokio::test]
+async fn test_broadcaster_delivery_modes_deliver_in_order() {
+    for delivery in [super::Delivery::Concurrent, super::Delivery::Unordered, super::Delivery::Sequential] {
+        let mut broadcaster = Broadcaster::builder()
+            .buffer_size(2)
+            .channel(TOKIO_CHANNEL)
+            .delivery(delivery)
+            .build();
+        let mut rxs = (0..3).map(|_| broadcaster.subscribe()).collect_vec();
+        // the dropped subscriber is pruned, and the others keep their own channels
+        drop(rxs.remove(1));
+        let (_, received) = tokio::join!(
+            async {
+                for message in 0..20 {
+                    broadcaster.broadcast_and_prune(message).await.unwrap();
+                }
+                assert_eq!(broadcaster.senders.len(), 2);
+                drop(broadcaster);
+            },
+            futures::future::join_all(rxs.iter_mut().map(|rx| async {
+                let mut received = Vec::new();
+                while let Some(message) = rx.recv().await {
+                    received.push(message);
+                }
+                received
+            })),
+        );
+        for received in received {
+            assert_eq!(received, (0..20).collect_vec(), "delivery: {delivery:?}");
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_sequential_delivery_waits_for_earlier_subscribers() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .delivery(super::Delivery::Sequential)
+        .build();
+    let mut rx0 = broadcaster.subscribe();
+    let mut rx1 = broadcaster.subscribe();
+    broadcaster.broadcast(0).await.unwrap();
+    let timeout = tokio::time::Duration::from_millis(10);
+    tokio::join!(
+        async { broadcaster.broadcast(1).await.unwrap() },
+        async {
+            assert_eq!(rx1.recv().await, Some(0));
+            // subscriber 0 hasn't made room for the next item yet, so subscriber 1 has to wait for it
+            assert!(tokio::time::timeout(timeout, rx1.recv()).await.is_err());
+            assert_eq!(rx0.recv().await, Some(0));
+            assert_eq!(rx1.recv().await, Some(1));
+        },
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_pause_and_resume() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let pause_token = broadcaster.get_pause_token().clone();
+    pause_token.pause();
+    let (_, received) = tokio::join!(
+        async {
+            broadcaster.broadcast(0).await.unwrap();
+            drop(broadcaster);
+        },
+        async {
+            let paused = tokio::time::timeout(tokio::time::Duration::from_secs(1), rx.recv()).await;
+            assert!(paused.is_err(), "nothing should be sent while paused");
+            pause_token.resume();
+            rx.recv().await
+        },
+    );
+    assert_eq!(received, Some(0));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_cancellation_takes_priority_over_pause() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let _rx = broadcaster.subscribe();
+    broadcaster.get_pause_token().pause();
+    let cancellation_token = broadcaster.get_cancellation_token().clone();
+    let (broadcast_result, _) = tokio::join!(broadcaster.broadcast(0), async {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        cancellation_token.cancel();
+    });
+    assert!(broadcast_result.is_err());
+    assert!(broadcaster.wait_until_resumed().await.is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_prefetch_pulls_ahead_of_slow_subscriber() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .prefetch_depth(4)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let pulled = std::sync::atomic::AtomicI32::new(0);
+    let stream = futures::stream::iter(0..10).map(|message| {
+        pulled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok::<_, String>(message)
+    });
+    let (broadcast_result, received) = tokio::join!(
+        async {
+            let broadcast_result = broadcaster.broadcast_from_result_stream(stream).await;
+            drop(broadcaster);
+            broadcast_result
+        },
+        async {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            // one item in the channel, one in flight, four prefetched and one waiting for room
+            assert_eq!(pulled.load(std::sync::atomic::Ordering::SeqCst), 7);
+            let mut received = Vec::new();
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+            }
+            received
+        },
+    );
+    assert!(broadcast_result.is_ok());
+    assert_eq!(received, (0..10).collect_vec());
+}
+
+#[tokio::test]
+async fn test_broadcaster_prefetch_keeps_errors_in_order() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .prefetch_depth(4)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let stream = futures::stream::iter([Ok(0), Ok(1), Err("failed"), Ok(3)]);
+    let broadcast_result = broadcaster.broadcast_from_result_stream(stream).await;
+    drop(broadcaster);
+    assert_eq!(broadcast_result, Err("failed"));
+    let mut received = Vec::new();
+    while let Some(message) = rx.recv().await {
+        received.push(message);
+    }
+    assert_eq!(received, vec![0, 1]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcast_handle_stream_prefetches_and_fails_completion() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .prefetch_depth(4)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let handle = broadcaster.into_handle(super::BroadcastOrdering::Total);
+    let pulled = std::sync::atomic::AtomicI32::new(0);
+    let stream = futures::stream::iter([Ok(0), Ok(1), Ok(2), Ok(3), Ok(4), Ok(5), Err("failed")]).inspect(|_| {
+        pulled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    });
+    let (broadcast_result, received) = tokio::join!(
+        async {
+            let broadcast_result = handle.broadcast_from_result_stream(stream).await;
+            drop(handle);
+            broadcast_result
+        },
+        async {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            // one item in the channel, one in flight, four prefetched and one waiting for room
+            assert_eq!(pulled.load(std::sync::atomic::Ordering::SeqCst), 7);
+            let mut received = Vec::new();
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+            }
+            received
+        },
+    );
+    assert_eq!(broadcast_result, Err("failed"));
+    assert_eq!(received, (0..6).collect_vec());
+    assert!(matches!(rx.completion(), Some(super::Completion::Failed)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_prefetch_respects_cancellation() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .prefetch_depth(4)
+        .build();
+    let _rx = broadcaster.subscribe();
+    let cancellation_token = broadcaster.get_cancellation_token().clone();
+    let stream = futures::stream::iter(0..).map(Ok::<_, String>);
+    let (broadcast_result, _) = tokio::join!(broadcaster.broadcast_from_result_stream(stream), async {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        cancellation_token.cancel();
+    });
+    assert!(broadcast_result.is_ok());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_adaptive_buffer_grows_within_budget() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .adaptive_buffer(super::AdaptiveBuffer::new(1, 16).with_budget(10))
+        .build();
+    let mut fast_rx = broadcaster.subscribe();
+    let mut slow_rx = broadcaster.subscribe();
+    let (buffer_sizes, _, _) = tokio::join!(
+        async {
+            for message in 0..50 {
+                broadcaster.broadcast(message).await.unwrap();
+            }
+            let buffer_sizes = broadcaster.buffer_sizes();
+            drop(broadcaster);
+            buffer_sizes
+        },
+        async { while fast_rx.recv().await.is_some() {} },
+        async {
+            while slow_rx.recv().await.is_some() {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        },
+    );
+    let [(_, fast_buffer_size), (_, slow_buffer_size)] = buffer_sizes[..] else {
+        panic!("expected two subscribers");
+    };
+    // the slow subscriber would grow to 16, but only gets what's left of the budget
+    assert!(fast_buffer_size <= 2, "fast buffer size: {fast_buffer_size}");
+    assert_eq!(fast_buffer_size + slow_buffer_size, 10);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_adaptive_buffer_budget_is_shared_across_broadcasters() {
+    let adaptive_buffer = super::AdaptiveBuffer::new(1, 16).with_budget(10);
+    let mut broadcasters = (0..2)
+        .map(|_| {
+            Broadcaster::builder()
+                .buffer_size(1)
+                .channel(TOKIO_CHANNEL)
+                .adaptive_buffer(adaptive_buffer.clone())
+                .build()
+        })
+        .collect_vec();
+    let mut rxs = broadcasters.iter_mut().map(Broadcaster::subscribe).collect_vec();
+    assert_eq!(adaptive_buffer.allocated(), 2);
+    tokio::join!(
+        futures::future::join_all(broadcasters.iter().map(|broadcaster| async move {
+            for message in 0..50 {
+                broadcaster.broadcast(message).await.unwrap();
+            }
+        })),
+        futures::future::join_all(rxs.iter_mut().map(|rx| async {
+            for _ in 0..50 {
+                rx.recv().await.unwrap();
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        })),
+    );
+    // both subscribers would grow to 16, but share one budget between them
+    let buffer_sizes = broadcasters
+        .iter()
+        .flat_map(Broadcaster::buffer_sizes)
+        .map(|(_, buffer_size)| buffer_size)
+        .collect_vec();
+    assert_eq!(buffer_sizes.iter().sum::<usize>(), 10);
+    drop(broadcasters);
+    assert_eq!(adaptive_buffer.allocated(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_adaptive_buffer_shrinks_once_caught_up() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .adaptive_buffer(super::AdaptiveBuffer::new(1, 8))
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let (_, (lagging_buffer_size, caught_up_buffer_size)) = tokio::join!(
+        async {
+            for message in 0..20 {
+                broadcaster.broadcast(message).await.unwrap();
+            }
+            // slow down enough for the subscriber to catch up
+            for message in 20..300 {
+                broadcaster.broadcast(message).await.unwrap();
+                tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+            }
+        },
+        async {
+            for _ in 0..20 {
+                rx.recv().await.unwrap();
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+            let lagging_buffer_size = broadcaster.buffer_sizes()[0].1;
+            for _ in 20..300 {
+                rx.recv().await.unwrap();
+            }
+            (lagging_buffer_size, broadcaster.buffer_sizes()[0].1)
+        },
+    );
+    assert_eq!(lagging_buffer_size, 8);
+    assert!(caught_up_buffer_size < 8, "buffer size: {caught_up_buffer_size}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_adaptive_buffer_grows_within_memory_budget() {
+    let memory_budget = super::MemoryBudget::new(40);
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(3)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .adaptive_buffer(super::AdaptiveBuffer::new(3, 16))
+        .memory_budget(memory_budget.clone())
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let (buffer_sizes, _) = tokio::join!(
+        async {
+            for _ in 0..50 {
+                broadcaster.broadcast(bytes::Bytes::from_static(&[0u8; 10])).await.unwrap();
+            }
+            let buffer_sizes = broadcaster.buffer_sizes();
+            drop(broadcaster);
+            buffer_sizes
+        },
+        async {
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        },
+    );
+    // doubling would take it to 6, but the memory budget only holds 4 items
+    let buffer_size = buffer_sizes[0].1;
+    assert_eq!(buffer_size, 4);
+    assert_eq!(memory_budget.in_use(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_adaptive_buffer_stops_limiting_unwrapped_receivers() {
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(TOKIO_CHANNEL)
+        .adaptive_buffer(super::AdaptiveBuffer::new(1, 4))
+        .build();
+    let mut rx = broadcaster.subscribe().into_inner();
+    let timeout = tokio::time::Duration::from_secs(1);
+    let received = tokio::time::timeout(timeout, async {
+        let (_, received) = tokio::join!(
+            async {
+                for message in 0..20 {
+                    broadcaster.broadcast(message).await.unwrap();
+                }
+                drop(broadcaster);
+            },
+            async {
+                let mut received = Vec::new();
+                while let Some(message) = rx.recv().await {
+                    received.push(message);
+                }
+                received
+            },
+        );
+        received
+    })
+    .await
+    .expect("the broadcaster shouldn't wait on a receiver that can't report what it has received");
+    assert_eq!(received, (0..20).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_broadcaster_completion_reports_failed_source() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let stream = futures::stream::iter([Ok(0), Err("failed")]);
+    assert!(broadcaster.broadcast_from_result_stream(stream).await.is_err());
+    drop(broadcaster);
+    assert_eq!(rx.recv().await, Some(0));
+    assert_eq!(rx.recv().await, None);
+    assert!(matches!(rx.completion(), Some(super::Completion::Failed)));
+}
+
+#[tokio::test]
+async fn test_broadcaster_completion_reports_finished_and_cancelled() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    broadcaster.broadcast_from_stream(futures::stream::iter([0, 1])).await;
+    drop(broadcaster);
+    while rx.recv().await.is_some() {}
+    assert!(rx.completion().is_some_and(|completion| completion.is_finished()));
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .build();
+    let (mut rx, cancellation_token) = broadcaster.subscribe_with_cancellation_token();
+    cancellation_token.cancel();
+    drop(broadcaster);
+    assert_eq!(rx.recv().await, None);
+    let Some(super::Completion::Cancelled(reason)) = rx.completion() else {
+        panic!("completion should be cancelled");
+    };
+    assert_eq!(reason.canceller(), super::Canceller::Subscriber(rx.subscriber_id()));
+}
+
+#[tokio::test]
+async fn test_broadcaster_deferred_completion_holds_end_of_stream() {
+    use crate::channel::receiver::Receiver;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(TOKIO_CHANNEL)
+        .defer_completion(true)
+        .build();
+    let mut rx = broadcaster.subscribe();
+    let completion_token = broadcaster.get_completion_token().clone();
+    drop(broadcaster);
+    let (end_of_stream, _) = tokio::join!(rx.recv(), async {
+        tokio::task::yield_now().await;
+        completion_token.fail();
+    });
+    assert_eq!(end_of_stream, None);
+    assert!(matches!(rx.completion(), Some(super::Completion::Failed)));
+}
+
+#[tokio::test]
+async fn test_broadcaster_envelopes_carry_sequence_and_offset() {
+    use crate::channel::{Envelope, EnvelopeChannel, SequenceChecked, receiver::Receiver};
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(EnvelopeChannel::with_offsets(
+            tokio::sync::mpsc::channel::<Envelope<bytes::Bytes>>,
+        ))
+        .build();
+    let mut early_rx = SequenceChecked::new(broadcaster.subscribe());
+    broadcaster.broadcast(bytes::Bytes::from_static(b"abc")).await.unwrap();
+    let mut late_rx = SequenceChecked::new(broadcaster.subscribe());
+    for chunk in [&b"de"[..], b"", b"fghi"] {
+        broadcaster.broadcast(bytes::Bytes::from_static(chunk)).await.unwrap();
+    }
+    drop(broadcaster);
+
+    let mut positions = Vec::new();
+    while let Some(envelope) = early_rx.recv().await {
+        positions.push((envelope.sequence, envelope.offset));
+    }
+    assert_eq!(
+        positions,
+        vec![(0, Some(0)), (1, Some(3)), (2, Some(5)), (3, Some(5))]
+    );
+    let first_late_envelope = late_rx.recv().await.unwrap();
+    assert_eq!(first_late_envelope.sequence, 1);
+    assert_eq!(first_late_envelope.into_inner(), bytes::Bytes::from_static(b"de"));
+}
+
+#[tokio::test]
+#[cfg_attr(not(debug_assertions), ignore = "the checks only run in debug builds")]
+#[should_panic(expected = "gap in envelopes")]
+async fn test_sequence_checked_catches_gaps() {
+    use crate::channel::{Envelope, SequenceChecked, receiver::Receiver};
+
+    let (tx, rx) = tokio::sync::mpsc::channel(10);
+    for sequence in [0, 2] {
+        tx.send(Envelope {
+            sequence,
+            offset: None,
+            timestamp: tokio::time::Instant::now(),
+            item: (),
+        })
+        .await
+        .unwrap();
+    }
+    let mut rx = SequenceChecked::new(rx);
+    while rx.recv().await.is_some() {}
+}
+
+#[tokio::test]
+async fn test_broadcaster_latest_channel_skips_stale_values() {
+    use crate::channel::Latest;
+
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(crate::channel::LatestChannel::<i32>::new())
+        .build();
+    let mut fast_rx = broadcaster.subscribe();
+    let mut slow_rx = broadcaster.subscribe();
+    broadcaster.broadcast(0).await.unwrap();
+    assert_eq!(fast_rx.recv().await.map(Latest::into_inner), Some(0));
+    // the slow subscriber never reads, so these would block any bounded channel
+    for message in 1..10 {
+        broadcaster.broadcast(message).await.unwrap();
+    }
+    assert_eq!(slow_rx.recv().await.map(Latest::into_inner), Some(9));
+    assert_eq!(fast_rx.recv().await.map(Latest::into_inner), Some(9));
+
+    // recv only returns once there's something new
+    let waiting = tokio::time::timeout(tokio::time::Duration::from_millis(10), fast_rx.recv()).await;
+    assert!(waiting.is_err());
+
+    broadcaster.broadcast(10).await.unwrap();
+    drop(broadcaster);
+    assert_eq!(fast_rx.recv().await.map(Latest::into_inner), Some(10));
+    assert_eq!(fast_rx.recv().await, None);
+}
+
+#[tokio::test]
+async fn test_broadcaster_resumes_subscribers_by_id() {
+    let progress_tracker = super::ProgressTracker::new(0, bytes::Bytes::len).with_resumed_offsets(
+        vec![
+            super::Progress {
+                subscriber_id: super::SubscriberId(1),
+                offset: 4,
+            },
+            super::Progress {
+                subscriber_id: super::SubscriberId(0),
+                offset: 2,
+            },
+        ],
+        bytes::Bytes::split_to,
+    );
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(3)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .progress_tracker(progress_tracker)
+        .build();
+    let mut rxs = (0..3).map(|_| broadcaster.subscribe()).collect_vec();
+    for chunk in [&b"ab"[..], b"cd", b"ef"] {
+        broadcaster.broadcast(bytes::Bytes::from_static(chunk)).await.unwrap();
+    }
+    drop(broadcaster);
+    let mut received = Vec::new();
+    for rx in &mut rxs {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.extend_from_slice(&chunk);
+        }
+        received.push(chunks);
+    }
+    // a subscriber without a resumed offset gets everything
+    assert_eq!(received, [&b"cdef"[..], b"ef", b"abcdef"]);
+}
+
+#[tokio::test]
+async fn test_broadcaster_latest_channel_counts_skipped_values_as_received() {
+    use crate::channel::receiver::Receiver;
+
+    let memory_budget = super::MemoryBudget::new(100);
+    let mut broadcaster = Broadcaster::builder()
+        .buffer_size(1)
+        .channel(crate::channel::LatestChannel::<bytes::Bytes>::new())
+        .memory_budget(memory_budget.clone())
+        .progress_tracker(super::ProgressTracker::new(0, bytes::Bytes::len))
+        .build();
+    let mut rx = broadcaster.subscribe();
+    for _ in 0..10 {
+        broadcaster.broadcast(bytes::Bytes::from_static(&[0u8; 3])).await.unwrap();
+    }
+    assert_eq!(memory_budget.in_use(), 30);
+
+    let latest = rx.recv().await.unwrap();
+    assert_eq!(latest.sequence, 9);
+    assert_eq!(rx.sequence(), Some(9));
+    // the skipped items no longer hold memory budget, and count towards the subscriber's progress
+    assert_eq!(memory_budget.in_use(), 0);
+    let progress = broadcaster.get_progress_tracker().unwrap().progress();
+    assert_eq!(progress[0].offset, 30);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_broadcaster_memory_budget_is_shared_across_broadcasters() {
+    let memory_budget = super::MemoryBudget::new(10);
+    let mut first = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .memory_budget(memory_budget.clone())
+        .build();
+    let mut second = Broadcaster::builder()
+        .buffer_size(10)
+        .channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .memory_budget(memory_budget.clone())
+        .build();
+    let mut first_rx = first.subscribe();
+    let second_rx = second.subscribe();
+
+    first.broadcast(bytes::Bytes::from_static(&[0u8; 6])).await.unwrap();
+    assert_eq!(memory_budget.in_use(), 6);
+    let timeout = tokio::time::Duration::from_millis(100);
+    let blocked =
+        tokio::time::timeout(timeout, second.broadcast(bytes::Bytes::from_static(&[0u8; 6]))).await;
+    assert!(blocked.is_err(), "the second broadcaster should wait for the first one's bytes to land");
+
+    first_rx.recv().await.unwrap();
+    assert_eq!(memory_budget.in_use(), 0);
+    second.broadcast(bytes::Bytes::from_static(&[0u8; 6])).await.unwrap();
+    assert_eq!(memory_budget.in_use(), 6);
+    // nobody is left to receive the item, so it no longer counts
+    drop(second);
+    drop(second_rx);
+    assert_eq!(memory_budget.in_use(), 0);
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+/// Broadcasts items that aren't [`Clone`] by wrapping each one in an [`Arc`] once, so subscribers receive `Arc<T>`.
+///
+/// Wraps any channel of `Arc<T>`, e.g. `ArcChannel::new(tokio::sync::mpsc::channel)`.
+#[derive(Debug, Clone, Copy)]
+pub struct ArcChannel<C>(C);
+
+impl<C> ArcChannel<C> {
+    pub fn new(channel: C) -> Self {
+        Self(channel)
+    }
+}
+
+impl<C, T> super::Channel for ArcChannel<C>
+where
+    C: super::Channel<Item = Arc<T>, Shared = Arc<T>>,
+{
+    type Item = T;
+    type Shared = Arc<T>;
+    type Sender = C::Sender;
+    type Receiver = C::Receiver;
+    fn create_channel(&self, buffer_size: usize) -> (Self::Sender, Self::Receiver) {
+        self.0.create_channel(buffer_size)
+    }
+    fn share(&self, item: Self::Item, _sequence: u64) -> Self::Shared {
+        Arc::new(item)
+    }
+}
+
+/// Helpers for subscribers of an [`ArcChannel`] that want to own the item.
+pub trait ArcItemExt<T> {
+    /// Returns the item if every other subscriber has already dropped it.
+    fn try_into_owned(self) -> Result<T, Arc<T>>;
+    /// Returns the item, cloning it if another subscriber still holds it.
+    fn into_owned(self) -> T
+    where
+        T: Clone;
+}
+
+impl<T> ArcItemExt<T> for Arc<T> {
+    fn try_into_owned(self) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(self)
+    }
+    fn into_owned(self) -> T
+    where
+        T: Clone,
+    {
+        Arc::unwrap_or_clone(self)
+    }
+}
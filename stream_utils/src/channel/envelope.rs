@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::broadcaster::ByteLen;
+
+/// An item along with where it sits in the broadcast.
+#[derive(Debug, Clone)]
+pub struct Envelope<T> {
+    /// Numbers the items of the broadcast from zero, the same way acknowledgements count them.
+    pub sequence: u64,
+    /// The number of bytes broadcast before this item, if the channel tracks offsets.
+    pub offset: Option<u64>,
+    /// When the item was handed to the broadcaster.
+    pub timestamp: tokio::time::Instant,
+    pub item: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+}
+
+/// Wraps each broadcast item in an [`Envelope`], so subscribers receive `Envelope<T>`.
+///
+/// Wraps any channel of `Envelope<T>`, e.g. `EnvelopeChannel::with_offsets(tokio::sync::mpsc::channel)`.
+#[derive(Debug)]
+pub struct EnvelopeChannel<C, T> {
+    channel: C,
+    offset: AtomicU64,
+    byte_len: Option<fn(&T) -> usize>,
+}
+
+impl<C, T> EnvelopeChannel<C, T> {
+    /// Envelopes carry a sequence number and a timestamp, but no offset.
+    pub fn new(channel: C) -> Self {
+        Self {
+            channel,
+            offset: AtomicU64::new(0),
+            byte_len: None,
+        }
+    }
+}
+
+impl<C, T> EnvelopeChannel<C, T>
+where
+    T: ByteLen,
+{
+    /// Envelopes also carry the byte offset of their item within the source.
+    pub fn with_offsets(channel: C) -> Self {
+        Self {
+            byte_len: Some(|item| item.byte_len()),
+            ..Self::new(channel)
+        }
+    }
+}
+
+// each broadcaster gets its own channel, so clones start counting offsets from zero again
+impl<C, T> Clone for EnvelopeChannel<C, T>
+where
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            channel: self.channel.clone(),
+            offset: AtomicU64::new(0),
+            byte_len: self.byte_len,
+        }
+    }
+}
+
+impl<C, T> super::Channel for EnvelopeChannel<C, T>
+where
+    C: super::Channel<Item = Envelope<T>, Shared = Envelope<T>>,
+    T: Clone,
+{
+    type Item = T;
+    type Shared = Envelope<T>;
+    type Sender = C::Sender;
+    type Receiver = C::Receiver;
+    fn create_channel(&self, buffer_size: usize) -> (Self::Sender, Self::Receiver) {
+        self.channel.create_channel(buffer_size)
+    }
+    fn share(&self, item: Self::Item, sequence: u64) -> Self::Shared {
+        let offset = self
+            .byte_len
+            .map(|byte_len| self.offset.fetch_add(byte_len(&item) as u64, Ordering::AcqRel));
+        Envelope {
+            sequence,
+            offset,
+            timestamp: tokio::time::Instant::now(),
+            item,
+        }
+    }
+}
+
+/// Checks, in debug builds, that a subscriber receives every envelope exactly once and in order.
+///
+/// The first envelope received sets the starting point, so late subscribers are fine. Broadcasting through a
+/// [`crate::broadcaster::BroadcastHandle`] only keeps envelopes in order with
+/// [`crate::broadcaster::BroadcastOrdering::Total`].
+#[derive(Debug)]
+pub struct SequenceChecked<Rx> {
+    rx: Rx,
+    // the sequence and the lowest offset the next envelope can have
+    expected: Option<(u64, Option<u64>)>,
+}
+
+impl<Rx> SequenceChecked<Rx> {
+    pub fn new(rx: Rx) -> Self {
+        Self { rx, expected: None }
+    }
+
+    pub fn into_inner(self) -> Rx {
+        self.rx
+    }
+}
+
+impl<Rx, T> super::receiver::Receiver for SequenceChecked<Rx>
+where
+    Rx: super::receiver::Receiver<Item = Envelope<T>>,
+{
+    type Item = Envelope<T>;
+    async fn recv(&mut self) -> Option<Self::Item> {
+        let envelope = self.rx.recv().await?;
+        if let Some((sequence, offset)) = self.expected {
+            debug_assert!(
+                envelope.sequence >= sequence,
+                "duplicate or reordered envelope: expected sequence {sequence}, got {}",
+                envelope.sequence,
+            );
+            debug_assert!(
+                envelope.sequence <= sequence,
+                "gap in envelopes: expected sequence {sequence}, got {}",
+                envelope.sequence,
+            );
+            debug_assert!(
+                envelope.offset >= offset,
+                "envelope offset went backwards: expected at least {offset:?}, got {:?}",
+                envelope.offset,
+            );
+        }
+        self.expected = Some((envelope.sequence + 1, envelope.offset));
+        Some(envelope)
+    }
+    fn sequence(&self) -> Option<u64> {
+        self.rx.sequence()
+    }
+    fn ack(&self, sequence: u64) {
+        self.rx.ack(sequence);
+    }
+    fn completion(&self) -> Option<crate::broadcaster::Completion> {
+        self.rx.completion()
+    }
+}
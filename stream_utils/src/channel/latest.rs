@@ -0,0 +1,94 @@
+/// Keeps only the latest item for each subscriber, backed by [`tokio::sync::watch`].
+///
+/// Sending never waits, so slow subscribers never hold up the broadcast; they skip straight to the latest item
+/// instead. `recv` waits for an item the subscriber hasn't seen yet, and the buffer size is ignored.
+///
+/// Items arrive as a [`Latest`], whose sequence number lets a subscription count the items it skipped as received,
+/// so acknowledgements, progress and memory budget all move past them.
+#[derive(Debug)]
+pub struct LatestChannel<T> {
+    item: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> LatestChannel<T> {
+    pub fn new() -> Self {
+        Self {
+            item: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T> Default for LatestChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for LatestChannel<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for LatestChannel<T> {}
+
+/// An item from a [`LatestChannel`], along with its sequence number in the broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Latest<T> {
+    pub sequence: u64,
+    pub item: T,
+}
+
+impl<T> Latest<T> {
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+}
+
+impl<T: Clone> super::Channel for LatestChannel<T> {
+    type Item = T;
+    type Shared = Latest<T>;
+    type Sender = LatestSender<T>;
+    type Receiver = LatestReceiver<T>;
+    fn create_channel(&self, _buffer_size: usize) -> (Self::Sender, Self::Receiver) {
+        // nothing has been sent yet, so subscribers wait for the first item
+        let (tx, rx) = tokio::sync::watch::channel(None);
+        (LatestSender(tx), LatestReceiver { rx, sequence: None })
+    }
+    fn share(&self, item: Self::Item, sequence: u64) -> Self::Shared {
+        Latest { sequence, item }
+    }
+}
+
+#[derive(Debug)]
+pub struct LatestSender<T>(tokio::sync::watch::Sender<Option<Latest<T>>>);
+
+impl<T> super::sender::Sender for LatestSender<T> {
+    type Item = Latest<T>;
+    async fn send(&self, item: Self::Item) -> super::sender::Result {
+        match self.0.send(Some(item)) {
+            Ok(()) => super::sender::Result::Success,
+            Err(_) => super::sender::Result::Failure,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LatestReceiver<T> {
+    rx: tokio::sync::watch::Receiver<Option<Latest<T>>>,
+    sequence: Option<u64>,
+}
+
+impl<T: Clone> super::receiver::Receiver for LatestReceiver<T> {
+    type Item = Latest<T>;
+    async fn recv(&mut self) -> Option<Self::Item> {
+        // an item sent just before the sender was dropped is still delivered
+        self.rx.changed().await.ok()?;
+        let latest = self.rx.borrow_and_update().clone()?;
+        self.sequence = Some(latest.sequence);
+        Some(latest)
+    }
+    fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+}
@@ -1,13 +1,30 @@
+mod arc;
+#[cfg(feature = "broadcaster")]
+mod envelope;
 mod impls;
+#[cfg(feature = "tokio")]
+mod latest;
 pub mod receiver;
 pub mod sender;
 
+pub use arc::{ArcChannel, ArcItemExt};
+#[cfg(feature = "broadcaster")]
+pub use envelope::{Envelope, EnvelopeChannel, SequenceChecked};
+#[cfg(feature = "tokio")]
+pub use latest::{Latest, LatestChannel, LatestReceiver, LatestSender};
+
 pub trait Channel {
+    /// The item handed to the broadcaster.
     type Item;
-    type Sender: sender::Sender<Item = Self::Item>;
-    type Receiver: receiver::Receiver<Item = Self::Item>;
+    /// The item subscribers receive. Each broadcast item is shared once, then cloned for every subscriber.
+    type Shared: Clone;
+    type Sender: sender::Sender<Item = Self::Shared>;
+    type Receiver: receiver::Receiver<Item = Self::Shared>;
 
     fn create_channel(&self, buffer_size: usize) -> (Self::Sender, Self::Receiver);
+    /// `sequence` numbers the items of a broadcast from zero, for channels that want to tell subscribers where the
+    /// item sits.
+    fn share(&self, item: Self::Item, sequence: u64) -> Self::Shared;
 }
 
 impl<T, F, Sender, Receiver> Channel for F
@@ -15,24 +32,33 @@ where
     F: Fn(usize) -> (Sender, Receiver),
     Sender: sender::Sender<Item = T>,
     Receiver: receiver::Receiver<Item = T>,
+    T: Clone,
 {
     type Item = T;
+    type Shared = T;
     type Sender = Sender;
     type Receiver = Receiver;
     fn create_channel(&self, buffer_size: usize) -> (Self::Sender, Self::Receiver) {
         (self)(buffer_size)
     }
+    fn share(&self, item: Self::Item, _sequence: u64) -> Self::Shared {
+        item
+    }
 }
 
 pub struct NoOpChannel<T> {
     item: std::marker::PhantomData<T>,
 }
 
-impl<T> Channel for NoOpChannel<T> {
+impl<T: Clone> Channel for NoOpChannel<T> {
     type Item = T;
+    type Shared = T;
     type Sender = sender::NoOpSender<Self::Item>;
     type Receiver = receiver::NoOpReceiver<Self::Item>;
     fn create_channel(&self, _buffer_size: usize) -> (Self::Sender, Self::Receiver) {
         (sender::NoOpSender::new(), receiver::NoOpReceiver::new())
     }
+    fn share(&self, item: Self::Item, _sequence: u64) -> Self::Shared {
+        item
+    }
 }
@@ -1,6 +1,23 @@
 pub trait Receiver {
     type Item;
     async fn recv(&mut self) -> Option<Self::Item>;
+    /// Sequence number of the most recently received item, if the receiver keeps count.
+    fn sequence(&self) -> Option<u64> {
+        None
+    }
+    /// Acknowledges that every item up to and including `sequence` has been processed.
+    ///
+    /// Does nothing unless the receiver subscribes to a broadcaster in ack mode.
+    fn ack(&self, _sequence: u64) {}
+    /// How the broadcast ended, once `recv` has returned `None`, if the receiver knows.
+    ///
+    /// A [`crate::broadcaster::Completion::Failed`] broadcast, or an [`crate::broadcaster::Completion::Evicted`]
+    /// subscriber, looks like any other end of stream to `recv`, so consumers that care about truncated data should
+    /// check this.
+    #[cfg(feature = "broadcaster")]
+    fn completion(&self) -> Option<crate::broadcaster::Completion> {
+        None
+    }
 }
 
 pub struct NoOpReceiver<T> {
@@ -15,6 +32,12 @@ impl<T> NoOpReceiver<T> {
     }
 }
 
+impl<T> Default for NoOpReceiver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Receiver for NoOpReceiver<T> {
     type Item = T;
     async fn recv(&mut self) -> Option<Self::Item> {
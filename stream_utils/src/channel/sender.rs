@@ -15,6 +15,12 @@ impl<T> NoOpSender<T> {
     }
 }
 
+impl<T> Default for NoOpSender<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Sender for NoOpSender<T> {
     type Item = T;
     async fn send(&self, _item: Self::Item) -> Result {
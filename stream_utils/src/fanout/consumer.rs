@@ -14,7 +14,7 @@ pub trait FanoutConsumer {
         Rx: channel::receiver::Receiver<Item = Self::Item>;
 }
 
-impl<'a, Consumer> FanoutConsumer for &'a Consumer
+impl<Consumer> FanoutConsumer for &Consumer
 where
     Consumer: FanoutConsumer
 {
@@ -37,13 +37,13 @@ where
 pub trait FanoutConsumerGroup {
     type Item;
     type Output: CancelEgress;
-    fn consume_from_fanout<'a, 'b, Channel>(
+    fn consume_from_fanout<'a, Channel>(
         &'a self,
-        fanout_broadcaster: &'b mut broadcaster::Broadcaster<Channel>,
+        fanout_broadcaster: &mut broadcaster::Broadcaster<Channel>,
         content_length: Option<u64>,
     ) -> impl Future<Output = Self::Output> + 'a
     where
-        Channel: channel::Channel<Item = Self::Item>,
+        Channel: channel::Channel<Shared = Self::Item>,
         Channel::Receiver: 'static,
     {
         self._consume_from_fanout(fanout_broadcaster, content_length)
@@ -59,7 +59,7 @@ pub trait FanoutConsumerGroup {
         impl Future<Output = Self::Output> + 'a,
     )
     where
-        Channel: channel::Channel<Item = Self::Item>,
+        Channel: channel::Channel<Shared = Self::Item>,
         Channel::Receiver: 'static;
     fn retry(self, previous_output: &Self::Output) -> Self;
 }
@@ -95,14 +95,15 @@ where
         impl Future<Output = Self::Output> + 'a,
     )
     where
-        Channel: channel::Channel<Item = Self::Item>,
+        Channel: channel::Channel<Shared = Self::Item>,
         Channel::Receiver: 'static,
     {
         let future = match self {
             Self::Consumer(consumer) => {
+                let (rx, cancellation_token) = fanout_broadcaster.subscribe_with_cancellation_token();
                 tokio_util::either::Either::Left(consumer.consume_from_fanout(
-                    fanout_broadcaster.subscribe(),
-                    fanout_broadcaster.get_cancellation_token().clone(),
+                    rx,
+                    cancellation_token,
                     content_length,
                 ))
             }
@@ -138,10 +139,11 @@ where
         impl Future<Output = Self::Output> + 'a,
     )
     where
-        Channel: channel::Channel<Item = Self::Item>,
+        Channel: channel::Channel<Shared = Self::Item>,
         Channel::Receiver: 'static,
     {
-        let future = self.consume_from_fanout(fanout_broadcaster.subscribe(), fanout_broadcaster.get_cancellation_token().clone(), content_length);
+        let (rx, cancellation_token) = fanout_broadcaster.subscribe_with_cancellation_token();
+        let future = self.consume_from_fanout(rx, cancellation_token, content_length);
         (fanout_broadcaster, future)
     }
     fn retry(self, _previous_output: &Self::Output) -> Self {
@@ -1,14 +1,38 @@
-use crate::{channel, fanout::consumer::CancelEgress};
+use crate::{broadcaster, channel, fanout::consumer::CancelEgress};
 
-pub struct StreamFanoutDriver<Source, Consumers, BroadcasterChannel, BroadcasterBufferSize, EgressSender> {
+// splits off the start of an item, as in `broadcaster::SplitBytes`
+type SplitTo<Item> = fn(&mut Item, usize) -> Item;
+
+pub struct StreamFanoutDriver<Source, Consumers, BroadcasterChannel, BroadcasterBufferSize, EgressSender>
+where
+    Source: super::source::FanoutSource,
+{
     stream_fanout: super::ReadyStreamFanout<Source, Consumers>,
     broadcaster_channel: BroadcasterChannel,
     broadcaster_buffer_size: BroadcasterBufferSize,
     egress_tx: EgressSender,
+    cancellation_token: broadcaster::CancellationToken,
+    pause_token: broadcaster::PauseToken,
+    rate_limit: Option<broadcaster::RateLimit<Source::Item>>,
+    ack_mode: bool,
+    send_deadline: Option<broadcaster::SendDeadline>,
+    stall_warning: Option<tokio::time::Duration>,
+    delivery: broadcaster::Delivery,
+    prefetch_depth: usize,
+    adaptive_buffer: Option<broadcaster::AdaptiveBuffer>,
+    memory_budget: Option<broadcaster::ItemBudget<Source::Item>>,
+    // weighs items for progress tracking and content length verification
+    byte_len: Option<fn(&Source::Item) -> usize>,
+    track_progress: bool,
+    // lines items up with where each consumer left off, once resumed
+    split_to: Option<SplitTo<Source::Item>>,
+    strict_content_length: bool,
 }
 
 impl<Source, Consumers>
     StreamFanoutDriver<Source, Consumers, (), (), ()>
+where
+    Source: super::source::FanoutSource,
 {
     pub(crate) fn new(
         stream_fanout: super::StreamFanout<Source, Consumers, super::Ready>,
@@ -18,6 +42,20 @@ impl<Source, Consumers>
             broadcaster_channel: (),
             broadcaster_buffer_size: (),
             egress_tx: (),
+            cancellation_token: broadcaster::CancellationToken::default(),
+            pause_token: broadcaster::PauseToken::default(),
+            rate_limit: None,
+            ack_mode: false,
+            send_deadline: None,
+            stall_warning: None,
+            delivery: broadcaster::Delivery::default(),
+            prefetch_depth: 0,
+            adaptive_buffer: None,
+            memory_budget: None,
+            byte_len: None,
+            track_progress: false,
+            split_to: None,
+            strict_content_length: false,
         }
     }
 }
@@ -39,18 +77,66 @@ where
             broadcaster_channel,
             broadcaster_buffer_size: self.broadcaster_buffer_size,
             egress_tx: self.egress_tx,
+            cancellation_token: self.cancellation_token,
+            pause_token: self.pause_token,
+            rate_limit: self.rate_limit,
+            ack_mode: self.ack_mode,
+            send_deadline: self.send_deadline,
+            stall_warning: self.stall_warning,
+            delivery: self.delivery,
+            prefetch_depth: self.prefetch_depth,
+            adaptive_buffer: self.adaptive_buffer,
+            memory_budget: self.memory_budget,
+            byte_len: self.byte_len,
+            track_progress: self.track_progress,
+            split_to: self.split_to,
+            strict_content_length: self.strict_content_length,
         }
     }
 }
 
 impl<Source, Consumers, BroadcasterChannel, OldBroadcasterBufferSize, EgressSender>
     StreamFanoutDriver<Source, Consumers, BroadcasterChannel, OldBroadcasterBufferSize, EgressSender>
+where
+    Source: super::source::FanoutSource,
 {
     pub fn with_broadcaster_buffer_size(
         self,
         broadcaster_buffer_size: usize,
     ) -> StreamFanoutDriver<Source, Consumers, BroadcasterChannel, usize, EgressSender> {
-        StreamFanoutDriver { stream_fanout: self.stream_fanout, broadcaster_channel: self.broadcaster_channel, broadcaster_buffer_size, egress_tx: self.egress_tx }
+        StreamFanoutDriver {
+            stream_fanout: self.stream_fanout,
+            broadcaster_channel: self.broadcaster_channel,
+            broadcaster_buffer_size,
+            egress_tx: self.egress_tx,
+            cancellation_token: self.cancellation_token,
+            pause_token: self.pause_token,
+            rate_limit: self.rate_limit,
+            ack_mode: self.ack_mode,
+            send_deadline: self.send_deadline,
+            stall_warning: self.stall_warning,
+            delivery: self.delivery,
+            prefetch_depth: self.prefetch_depth,
+            adaptive_buffer: None,
+            memory_budget: self.memory_budget,
+            byte_len: self.byte_len,
+            track_progress: self.track_progress,
+            split_to: self.split_to,
+            strict_content_length: self.strict_content_length,
+        }
+    }
+
+    /// Sizes each consumer's buffer based on how far it lags, instead of a fixed size; the final sizes are reported
+    /// through tracing.
+    pub fn with_adaptive_broadcaster_buffer_size(
+        self,
+        adaptive_buffer: broadcaster::AdaptiveBuffer,
+    ) -> StreamFanoutDriver<Source, Consumers, BroadcasterChannel, usize, EgressSender> {
+        let buffer_size = adaptive_buffer.max();
+        StreamFanoutDriver {
+            adaptive_buffer: Some(adaptive_buffer),
+            ..self.with_broadcaster_buffer_size(buffer_size)
+        }
     }
 }
 
@@ -65,13 +151,138 @@ where
     ) -> StreamFanoutDriver<Source, Consumers, BroadcasterChannel, BroadcasterBufferSize, EgressSender>
     where
         EgressSender: channel::sender::Sender<Item = EgressItem>,
-        EgressItem: super::egress::EgressItem<Source::Item>,
     {
         StreamFanoutDriver {
             stream_fanout: self.stream_fanout,
             broadcaster_channel: self.broadcaster_channel,
             broadcaster_buffer_size: self.broadcaster_buffer_size,
             egress_tx,
+            cancellation_token: self.cancellation_token,
+            pause_token: self.pause_token,
+            rate_limit: self.rate_limit,
+            ack_mode: self.ack_mode,
+            send_deadline: self.send_deadline,
+            stall_warning: self.stall_warning,
+            delivery: self.delivery,
+            prefetch_depth: self.prefetch_depth,
+            adaptive_buffer: self.adaptive_buffer,
+            memory_budget: self.memory_budget,
+            byte_len: self.byte_len,
+            track_progress: self.track_progress,
+            split_to: self.split_to,
+            strict_content_length: self.strict_content_length,
+        }
+    }
+}
+
+impl<Source, Consumers, BroadcasterChannel, BroadcasterBufferSize, EgressSender>
+    StreamFanoutDriver<Source, Consumers, BroadcasterChannel, BroadcasterBufferSize, EgressSender>
+where
+    Source: super::source::FanoutSource,
+{
+    /// Lets the caller cancel the fanout; the cancellation is reported as [`broadcaster::Canceller::External`].
+    pub fn with_cancellation_token(
+        self,
+        cancellation_token: broadcaster::CancellationToken,
+    ) -> Self {
+        Self {
+            cancellation_token,
+            ..self
+        }
+    }
+
+    /// Returns a token that pauses and resumes the fanout once it's driven; the source isn't pulled from while paused.
+    pub fn pause_token(&self) -> broadcaster::PauseToken {
+        self.pause_token.clone()
+    }
+
+    /// Lets consumers acknowledge what they've processed; the acknowledgements are reported by
+    /// [`super::UsedStreamFanout::acknowledgements`] once the fanout is done, however it ended.
+    pub fn with_ack_mode(self) -> Self {
+        Self {
+            ack_mode: true,
+            ..self
+        }
+    }
+
+    /// Throttles the source without it having to know; see [`broadcaster::RateLimit`].
+    pub fn with_rate_limit(self, rate_limit: broadcaster::RateLimit<Source::Item>) -> Self {
+        Self {
+            rate_limit: Some(rate_limit),
+            ..self
+        }
+    }
+
+    /// Stops a single slow consumer from holding up the rest; see [`broadcaster::SendDeadline`].
+    pub fn with_send_deadline(self, send_deadline: broadcaster::SendDeadline) -> Self {
+        Self {
+            send_deadline: Some(send_deadline),
+            ..self
+        }
+    }
+
+    /// Warns (with the `tracing` feature) about any consumer that has blocked the source for this long, repeating
+    /// for as long as it stays blocked.
+    pub fn with_stall_warning(self, stall_warning: tokio::time::Duration) -> Self {
+        Self {
+            stall_warning: Some(stall_warning),
+            ..self
+        }
+    }
+
+    /// Shares a cap on the bytes in flight with every other driver given the same budget; see
+    /// [`broadcaster::MemoryBudget`].
+    pub fn with_memory_budget(
+        self,
+        memory_budget: impl Into<broadcaster::ItemBudget<Source::Item>>,
+    ) -> Self {
+        Self {
+            memory_budget: Some(memory_budget.into()),
+            ..self
+        }
+    }
+
+    /// Counts the bytes each consumer receives, so a failed fanout can pick up where it left off with
+    /// [`super::UsedStreamFanout::resume`], and once it has, skips what each consumer already received.
+    pub fn with_progress_tracking(self) -> Self
+    where
+        Source::Item: broadcaster::SplitBytes,
+    {
+        Self {
+            byte_len: Some(broadcaster::ByteLen::byte_len),
+            track_progress: true,
+            split_to: Some(broadcaster::SplitBytes::split_to),
+            ..self
+        }
+    }
+
+    /// Fails the fanout with a [`super::FanoutError::LengthMismatch`] if the source broadcasts a different number of
+    /// bytes than its content length; consumers see the source fail, and the egress gets an error.
+    ///
+    /// Sources without a content length, and fanouts that are cancelled, aren't checked.
+    pub fn with_strict_content_length(self) -> Self
+    where
+        Source::Item: broadcaster::ByteLen,
+    {
+        Self {
+            byte_len: Some(broadcaster::ByteLen::byte_len),
+            strict_content_length: true,
+            ..self
+        }
+    }
+
+    /// Chooses how each item is handed to the consumers; see [`broadcaster::Delivery`].
+    pub fn with_delivery(self, delivery: broadcaster::Delivery) -> Self {
+        Self { delivery, ..self }
+    }
+
+    /// Lets stream sources read up to this many items ahead of the slowest consumer, so upstream I/O doesn't sit idle
+    /// while the consumers catch up. Sources wrapped in another, like [`super::source::Retrying`], read ahead just as
+    /// far.
+    pub fn with_prefetch_depth(self, prefetch_depth: usize) -> Self {
+        Self {
+            prefetch_depth,
+            ..self
         }
     }
 }
@@ -80,26 +291,65 @@ impl<Source, Consumers, BroadcasterChannel, EgressItem, EgressSender>
     StreamFanoutDriver<Source, Consumers, BroadcasterChannel, usize, EgressSender>
 where
     Source: super::source::FanoutSource,
-    Consumers: super::consumer::FanoutConsumerGroup<Item = Source::Item>,
+    Consumers: super::consumer::FanoutConsumerGroup<Item = BroadcasterChannel::Shared>,
     BroadcasterChannel: channel::Channel<Item = Source::Item>,
     BroadcasterChannel::Receiver: 'static,
-    EgressItem: super::egress::EgressItem<BroadcasterChannel::Item>,
+    EgressItem: super::egress::EgressItem<BroadcasterChannel::Shared>,
     EgressSender: super::egress::EgressSender<Item = EgressItem>,
 {
     pub async fn drive(
         mut self,
-    ) -> (super::UsedStreamFanout<Source, Consumers>, Result<Consumers::Output, Source::Error>) {
+    ) -> (
+        super::UsedStreamFanout<Source, Consumers>,
+        Result<Consumers::Output, super::FanoutError<Source::Error>>,
+    ) {
+        let fanout_broadcaster = broadcaster::Broadcaster::builder()
+            .channel(self.broadcaster_channel)
+            .buffer_size(self.broadcaster_buffer_size)
+            .cancellation_token(self.cancellation_token.clone())
+            .pause_token(self.pause_token)
+            // resolved by the fanout once the source returns, since custom sources can fail without telling it
+            .defer_completion(true)
+            .maybe_rate_limit(self.rate_limit)
+            .ack_mode(self.ack_mode)
+            .maybe_send_deadline(self.send_deadline)
+            .maybe_stall_warning(self.stall_warning)
+            .delivery(self.delivery)
+            .prefetch_depth(self.prefetch_depth)
+            .maybe_adaptive_buffer(self.adaptive_buffer)
+            .maybe_memory_budget(self.memory_budget)
+            .maybe_progress_tracker(self.byte_len.map(|byte_len| {
+                let progress_tracker =
+                    broadcaster::ProgressTracker::new(self.stream_fanout.state.start_offset, byte_len);
+                match self.split_to {
+                    Some(split_to) => progress_tracker
+                        .with_resumed_offsets(std::mem::take(&mut self.stream_fanout.state.resumed_offsets), split_to),
+                    None => progress_tracker,
+                }
+            }))
+            .build();
+        let ack_tracker = fanout_broadcaster.get_ack_tracker().cloned();
+        let progress_tracker = fanout_broadcaster.get_progress_tracker().cloned();
         let fanout_result = self.stream_fanout
             .drive_inner::<BroadcasterChannel, EgressItem, EgressSender>(
-                self.broadcaster_channel,
-                self.broadcaster_buffer_size,
+                fanout_broadcaster,
                 &self.egress_tx,
+                self.strict_content_length,
             )
             .await;
+        let cancellation_reason = self.cancellation_token.reason();
         if fanout_result.is_err() || fanout_result.as_ref().is_ok_and(Consumers::Output::cancel_egress) {
-            self.egress_tx.send(EgressItem::error()).await;
+            self.egress_tx.send(EgressItem::error(cancellation_reason.clone())).await;
         }
 
-        (self.stream_fanout.into_used(), fanout_result)
+        let used = super::Used {
+            cancellation_reason,
+            acknowledgements: ack_tracker.as_ref().map(broadcaster::AckTracker::acknowledgements),
+            progress: progress_tracker
+                .as_ref()
+                .filter(|_| self.track_progress)
+                .map(broadcaster::ProgressTracker::progress),
+        };
+        (self.stream_fanout.into_used(used), fanout_result)
     }
 }
@@ -1,44 +1,52 @@
-use crate::channel::receiver::Receiver;
+use crate::{broadcaster::CancellationReason, channel::receiver::Receiver};
 
 pub trait EgressItem<BroadcastItem> {
     fn from_broadcast_item(item: BroadcastItem) -> Self;
-    fn error() -> Self;
+    /// `cancellation_reason` is set if the fanout was cancelled rather than failing on its own.
+    fn error(cancellation_reason: Option<CancellationReason>) -> Self;
 }
 
 #[derive(thiserror::Error, Debug)]
 #[error("an error occurred")]
-pub struct GenericError;
+pub struct GenericError {
+    #[source]
+    cancellation_reason: Option<CancellationReason>,
+}
+
+impl GenericError {
+    pub fn cancellation_reason(&self) -> Option<&CancellationReason> {
+        self.cancellation_reason.as_ref()
+    }
+}
 
 impl<BroadcastItem> EgressItem<BroadcastItem> for Result<BroadcastItem, GenericError> {
     fn from_broadcast_item(item: BroadcastItem) -> Self {
         Self::Ok(item)
     }
-    fn error() -> Self {
-        Self::Err(GenericError)
+    fn error(cancellation_reason: Option<CancellationReason>) -> Self {
+        Self::Err(GenericError {
+            cancellation_reason,
+        })
     }
 }
 
 impl<T> EgressItem<T> for () {
-    fn from_broadcast_item(_item: T) -> Self {
-        ()
-    }
-    fn error() -> Self {
-        ()
-    }
+    fn from_broadcast_item(_item: T) -> Self {}
+    fn error(_cancellation_reason: Option<CancellationReason>) -> Self {}
 }
 
 pub trait EgressSender {
     type Item;
     async fn send(&self, item: Self::Item) -> crate::channel::sender::Result;
 
-    fn send_from_broadcaster<'a, 'b, BroadcasterChannel>(
+    fn send_from_broadcaster<'a, BroadcasterChannel>(
         &'a self,
-        broadcaster: &'b mut crate::broadcaster::Broadcaster<BroadcasterChannel>,
+        broadcaster: &mut crate::broadcaster::Broadcaster<BroadcasterChannel>,
     ) -> impl Future<Output = ()> + 'a
     where
         BroadcasterChannel: crate::channel::Channel,
         BroadcasterChannel::Receiver: 'static,
-        Self::Item: EgressItem<BroadcasterChannel::Item>,
+        Self::Item: EgressItem<BroadcasterChannel::Shared>,
     {
         self._send_from_broadcaster(broadcaster).1
     }
@@ -52,7 +60,7 @@ pub trait EgressSender {
     where
         BroadcasterChannel: crate::channel::Channel,
         BroadcasterChannel::Receiver: 'static,
-        Self::Item: EgressItem<BroadcasterChannel::Item>;
+        Self::Item: EgressItem<BroadcasterChannel::Shared>;
 }
 
 impl<Tx> EgressSender for Tx
@@ -73,12 +81,16 @@ where
     where
         BroadcasterChannel: crate::channel::Channel,
         BroadcasterChannel::Receiver: 'static,
-        Self::Item: EgressItem<BroadcasterChannel::Item>,
+        Self::Item: EgressItem<BroadcasterChannel::Shared>,
     {
         let mut rx = broadcaster.subscribe();
         let future = async move {
             while let Some(chunk) = rx.recv().await {
                 self.send(Self::Item::from_broadcast_item(chunk)).await;
+                // handing the item off counts as processing it
+                if let Some(sequence) = rx.sequence() {
+                    rx.ack(sequence);
+                }
             }
         };
         (broadcaster, future)
@@ -8,8 +8,35 @@ pub mod driver;
 pub mod egress;
 pub mod source;
 
-pub struct Ready;
-pub struct Used;
+/// The source broadcast a different number of bytes than its content length said it would.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("expected {expected} bytes from the source, but it broadcast {actual}")]
+pub struct LengthMismatch {
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// Why a driven fanout failed: the source failed, or what it broadcast didn't pass one of the driver's checks.
+#[derive(thiserror::Error, Debug)]
+pub enum FanoutError<E> {
+    #[error(transparent)]
+    Source(E),
+    /// With [`driver::StreamFanoutDriver::with_strict_content_length`].
+    #[error(transparent)]
+    LengthMismatch(#[from] LengthMismatch),
+}
+
+pub struct Ready {
+    // where in the source's content the fanout starts, once it's resumed
+    start_offset: u64,
+    // how far each consumer got before the fanout was resumed
+    resumed_offsets: Vec<broadcaster::Progress>,
+}
+pub struct Used {
+    cancellation_reason: Option<broadcaster::CancellationReason>,
+    acknowledgements: Option<Vec<broadcaster::Acknowledgement>>,
+    progress: Option<Vec<broadcaster::Progress>>,
+}
 
 pub type ReadyStreamFanout<Source, Consumers> = StreamFanout<Source, Consumers, Ready>;
 pub type UsedStreamFanout<Source, Consumers> = StreamFanout<Source, Consumers, Used>;
@@ -17,7 +44,7 @@ pub type UsedStreamFanout<Source, Consumers> = StreamFanout<Source, Consumers, U
 pub struct StreamFanout<Source, Consumers, State> {
     source: Source,
     consumers: Consumers,
-    state: std::marker::PhantomData<State>,
+    state: State,
 }
 
 impl<Source, Consumers, State> StreamFanout<Source, Consumers, State> {
@@ -31,10 +58,23 @@ impl<Source, Consumers> ReadyStreamFanout<Source, Consumers> {
         Self {
             source,
             consumers,
-            state: std::marker::PhantomData,
+            state: Ready {
+                start_offset: 0,
+                resumed_offsets: Vec::new(),
+            },
         }
     }
 
+    /// How many bytes into the source's content the fanout starts; non-zero once it's been resumed.
+    pub fn start_offset(&self) -> u64 {
+        self.state.start_offset
+    }
+}
+
+impl<Source, Consumers> ReadyStreamFanout<Source, Consumers>
+where
+    Source: FanoutSource,
+{
     pub fn into_driver(self) -> driver::StreamFanoutDriver<Source, Consumers, (), (), ()> {
         driver::StreamFanoutDriver::new(self)
     }
@@ -43,30 +83,29 @@ impl<Source, Consumers> ReadyStreamFanout<Source, Consumers> {
 impl<Source, Consumers> ReadyStreamFanout<Source, Consumers>
 where
     Source: FanoutSource,
-    Consumers: consumer::FanoutConsumerGroup<Item = Source::Item>,
 {
     #[cfg_attr(feature = "tracing", tracing::instrument(name = "stream_fanout", skip_all))]
     async fn drive_inner<BroadcasterChannel, EgressItem, EgressSender>(
         &mut self,
-        broadcaster_channel: BroadcasterChannel,
-        broadcaster_buffer_size: usize,
+        mut fanout_broadcaster: broadcaster::Broadcaster<BroadcasterChannel>,
         egress_tx: &EgressSender,
-    ) -> Result<Consumers::Output, Source::Error>
+        strict_content_length: bool,
+    ) -> Result<Consumers::Output, FanoutError<Source::Error>>
     where
+        Consumers: consumer::FanoutConsumerGroup<Item = BroadcasterChannel::Shared>,
         BroadcasterChannel: channel::Channel<Item = Source::Item>,
         BroadcasterChannel::Receiver: 'static,
-        EgressItem: egress::EgressItem<BroadcasterChannel::Item>,
+        EgressItem: egress::EgressItem<BroadcasterChannel::Shared>,
         EgressSender: egress::EgressSender<Item = EgressItem>,
     {
         #[cfg(feature = "tracing")]
         let start = tokio::time::Instant::now();
 
-        let content_length = self.source.get_content_length().await?;
-
-        let mut fanout_broadcaster = broadcaster::Broadcaster::builder()
-            .channel(broadcaster_channel)
-            .buffer_size(broadcaster_buffer_size)
-            .build();
+        let content_length = self
+            .source
+            .get_content_length()
+            .await
+            .map_err(FanoutError::Source)?;
 
         // create subscriber futures
         let consumers_future = self
@@ -74,8 +113,34 @@ where
             .consume_from_fanout(&mut fanout_broadcaster, content_length);
         let egress_future = egress_tx.send_from_broadcaster(&mut fanout_broadcaster);
 
-        // broadcast from stream
-        let stream_broadcast_future = self.source.broadcast(fanout_broadcaster);
+        // broadcast from stream, check it was all there, then tell the consumers how it went
+        let completion_token = fanout_broadcaster.get_completion_token().clone();
+        let cancellation_token = fanout_broadcaster.get_cancellation_token().clone();
+        let progress_tracker = fanout_broadcaster.get_progress_tracker().cloned();
+        let stream_broadcast_future = async {
+            let stream_broadcast_result = self
+                .source
+                .broadcast(fanout_broadcaster)
+                .await
+                .map_err(FanoutError::Source)
+                .and_then(|()| {
+                    let (true, Some(expected), Some(progress_tracker)) =
+                        (strict_content_length, content_length, &progress_tracker)
+                    else {
+                        return Ok(());
+                    };
+                    let actual = progress_tracker.bytes_broadcast();
+                    if actual == expected || cancellation_token.is_cancelled() {
+                        return Ok(());
+                    }
+                    Err(FanoutError::LengthMismatch(LengthMismatch { expected, actual }))
+                });
+            match stream_broadcast_result {
+                Ok(()) => completion_token.finish(),
+                Err(_) => completion_token.fail(),
+            }
+            stream_broadcast_result
+        };
 
         // poll futures concurrently
         let (stream_broadcast_result, consumers_output, _) =
@@ -93,7 +158,73 @@ where
         Ok(consumers_output)
     }
 
-    fn into_used(self) -> UsedStreamFanout<Source, Consumers> {
-        StreamFanout { source: self.source, consumers: self.consumers, state: std::marker::PhantomData }
+    fn into_used(self, used: Used) -> UsedStreamFanout<Source, Consumers> {
+        StreamFanout {
+            source: self.source,
+            consumers: self.consumers,
+            state: used,
+        }
     }
 }
+
+impl<Source, Consumers> UsedStreamFanout<Source, Consumers> {
+    /// Why the fanout was cancelled, if a consumer or the driver's caller cancelled it.
+    pub fn cancellation_reason(&self) -> Option<&broadcaster::CancellationReason> {
+        self.state.cancellation_reason.as_ref()
+    }
+
+    /// How far each consumer got, if the fanout was driven in ack mode.
+    ///
+    /// Consumers subscribe in the order the consumer group lists them, followed by the egress sender.
+    pub fn acknowledgements(&self) -> Option<&[broadcaster::Acknowledgement]> {
+        self.state.acknowledgements.as_deref()
+    }
+
+    /// The number of items every consumer has acknowledged, if the fanout was driven in ack mode.
+    pub fn low_watermark(&self) -> Option<u64> {
+        self.acknowledgements()?
+            .iter()
+            .map(|acknowledgement| acknowledgement.acknowledged)
+            .min()
+    }
+
+    /// How far into the source's content each consumer got, if the fanout was driven with progress tracking.
+    ///
+    /// Consumers are listed in the same order as [`Self::acknowledgements`].
+    pub fn progress(&self) -> Option<&[broadcaster::Progress]> {
+        self.state.progress.as_deref()
+    }
+
+    /// The offset every consumer received up to, if the fanout was driven with progress tracking.
+    pub fn resume_offset(&self) -> Option<u64> {
+        self.progress()?
+            .iter()
+            .map(|progress| progress.offset)
+            .min()
+    }
+
+    /// Picks the source back up at [`Self::resume_offset`] instead of starting over; `None` without progress
+    /// tracking, or if the source can't resume.
+    ///
+    /// Driven with progress tracking again, each consumer skips the bytes it already received, so consumers that got
+    /// further than the others don't receive any twice. Every consumer has to carry on from what it already received,
+    /// since it's only sent the rest.
+    pub fn resume(self) -> Option<ReadyStreamFanout<Source, Consumers>>
+    where
+        Source: FanoutSource,
+    {
+        let start_offset = self.resume_offset()?;
+        let resumed_offsets = self.progress()?.to_vec();
+        Some(StreamFanout {
+            source: self.source.resume_from(start_offset)?,
+            consumers: self.consumers,
+            state: Ready {
+                start_offset,
+                resumed_offsets,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
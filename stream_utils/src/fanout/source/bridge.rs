@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{broadcaster, channel};
+
+/// Runs `source` against a broadcaster of its own and forwards what it broadcasts to `fanout_broadcaster`, showing
+/// each item to `inspect` on the way, for wrappers that need to see the items or outlive a broadcast.
+pub(super) async fn broadcast_through<S, Channel>(
+    source: &mut S,
+    fanout_broadcaster: &broadcaster::Broadcaster<Channel>,
+    mut inspect: impl Inspect<S::Item>,
+) -> Result<(), S::Error>
+where
+    S: super::FanoutSource,
+    Channel: channel::Channel<Item = S::Item>,
+{
+    let (source_broadcaster, mut rx) = bridge(fanout_broadcaster);
+    let forward = async {
+        while let Some(item) = rx.recv().await {
+            inspect.inspect(&item).await;
+            if fanout_broadcaster.broadcast(item).await.is_err() {
+                break;
+            }
+        }
+    };
+    let (result, ()) = tokio::join!(source.broadcast(source_broadcaster), forward);
+    result
+}
+
+/// A broadcaster for a source to run against in place of the fanout's, and a receiver for what the source broadcasts
+/// through it. It shares the fanout's cancellation and pause tokens, so the source stops and pauses along with the
+/// fanout, and its prefetch depth, so the source reads ahead as if it broadcast to the fanout directly.
+pub(super) fn bridge<T, Channel>(
+    fanout_broadcaster: &broadcaster::Broadcaster<Channel>,
+) -> (broadcaster::Broadcaster<Bridge<T>>, BridgeReceiver<T>)
+where
+    Channel: channel::Channel,
+{
+    let mut source_broadcaster = broadcaster::Broadcaster::builder()
+        .channel(Bridge::default())
+        .buffer_size(1)
+        .cancellation_token(fanout_broadcaster.get_cancellation_token().clone())
+        .pause_token(fanout_broadcaster.get_pause_token().clone())
+        .prefetch_depth(fanout_broadcaster.prefetch_depth())
+        .build();
+    let rx = BridgeReceiver(source_broadcaster.subscribe());
+    (source_broadcaster, rx)
+}
+
+pub(super) struct BridgeReceiver<T>(broadcaster::Subscription<tokio::sync::mpsc::Receiver<Handoff<T>>>);
+
+impl<T> BridgeReceiver<T> {
+    pub(super) async fn recv(&mut self) -> Option<T> {
+        Some(self.0.recv().await?.take())
+    }
+
+    #[cfg(any(feature = "brotli", feature = "gzip", feature = "zstd"))]
+    pub(super) fn into_stream(self) -> impl futures::Stream<Item = T> {
+        futures::stream::unfold(self, |mut rx| async move { Some((rx.recv().await?, rx)) })
+    }
+}
+
+/// Sees each item a bridged source broadcasts, before it's forwarded.
+pub(super) trait Inspect<Item> {
+    async fn inspect(&mut self, item: &Item);
+}
+
+impl<Item, F> Inspect<Item> for F
+where
+    F: FnMut(&Item),
+{
+    async fn inspect(&mut self, item: &Item) {
+        self(item)
+    }
+}
+
+/// Hands each item to the forwarding loop as is; it's the only subscriber, so items don't need to be cloned.
+pub(super) struct Bridge<T> {
+    item: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Bridge<T> {
+    fn default() -> Self {
+        Self {
+            item: std::marker::PhantomData,
+        }
+    }
+}
+
+pub(super) struct Handoff<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> Handoff<T> {
+    fn take(self) -> T {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .expect("each item is handed off once")
+    }
+}
+
+impl<T> Clone for Handoff<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> channel::Channel for Bridge<T> {
+    type Item = T;
+    type Shared = Handoff<T>;
+    type Sender = tokio::sync::mpsc::Sender<Handoff<T>>;
+    type Receiver = tokio::sync::mpsc::Receiver<Handoff<T>>;
+    fn create_channel(&self, buffer_size: usize) -> (Self::Sender, Self::Receiver) {
+        tokio::sync::mpsc::channel(buffer_size)
+    }
+    fn share(&self, item: Self::Item, _sequence: u64) -> Self::Shared {
+        Handoff(Arc::new(Mutex::new(Some(item))))
+    }
+}
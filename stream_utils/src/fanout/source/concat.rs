@@ -0,0 +1,91 @@
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+/// A part of a [`Concat`] source failed; parts are numbered from zero.
+#[derive(thiserror::Error, Debug)]
+#[error("part {part} of the concatenated source failed")]
+pub struct ConcatError<E> {
+    pub part: usize,
+    #[source]
+    pub error: E,
+}
+
+/// Broadcasts several sources one after the other, as a single stream.
+///
+/// The content length is the sum of the parts' lengths, if every part knows its own. Getting it asks every part for
+/// its length up front, so parts that open a connection to find out hold it until their turn comes.
+pub struct Concat<S> {
+    parts: Vec<S>,
+}
+
+impl<S> Concat<S>
+where
+    S: FanoutSource,
+{
+    pub fn new(parts: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            parts: parts.into_iter().collect(),
+        }
+    }
+
+    pub fn parts(&self) -> &[S] {
+        &self.parts
+    }
+
+    pub fn into_parts(self) -> Vec<S> {
+        self.parts
+    }
+}
+
+impl<S> FromIterator<S> for Concat<S>
+where
+    S: FanoutSource,
+{
+    fn from_iter<I: IntoIterator<Item = S>>(parts: I) -> Self {
+        Self::new(parts)
+    }
+}
+
+impl<S> FanoutSource for Concat<S>
+where
+    S: FanoutSource,
+{
+    type Item = S::Item;
+    type Error = ConcatError<S::Error>;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        let mut content_length = Some(0);
+        for (part, source) in self.parts.iter_mut().enumerate() {
+            let part_length = source
+                .get_content_length()
+                .await
+                .map_err(|error| ConcatError { part, error })?;
+            content_length = content_length.zip(part_length).map(|(total, part_length)| total + part_length);
+        }
+        Ok(content_length)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        for (part, source) in self.parts.iter_mut().enumerate() {
+            if broadcaster.get_cancellation_token().is_cancelled() {
+                break;
+            }
+            // each part gets a broadcaster of its own, so the fanout's lasts until the final part is done
+            super::bridge::broadcast_through(source, &broadcaster, |_: &S::Item| {})
+                .await
+                .map_err(|error| ConcatError { part, error })?;
+        }
+        Ok(())
+    }
+    /// Only resets if every part can.
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            parts: self.parts.into_iter().map(S::reset).collect::<Option<_>>()?,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use futures::StreamExt;
+
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+#[derive(thiserror::Error, Debug)]
+pub enum DecompressError<E> {
+    #[error(transparent)]
+    Source(E),
+    #[error("the source's content couldn't be decompressed")]
+    Corrupt(#[source] std::io::Error),
+}
+
+/// Decompresses a source once, before it's broadcast, rather than in every consumer.
+///
+/// The decompressed length isn't known up front, so the content length is always unknown, and offsets into the
+/// decompressed content don't map onto the source's, so it can't resume.
+pub struct Decompress<S> {
+    source: S,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    #[cfg(feature = "brotli")]
+    Brotli,
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl<S> Decompress<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    #[cfg(feature = "brotli")]
+    pub fn brotli(source: S) -> Self {
+        Self {
+            source,
+            format: Format::Brotli,
+        }
+    }
+
+    /// Carries on through every member of a multi-member gzip stream.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(source: S) -> Self {
+        Self {
+            source,
+            format: Format::Gzip,
+        }
+    }
+
+    /// Carries on through every frame of a multi-frame zstd stream.
+    #[cfg(feature = "zstd")]
+    pub fn zstd(source: S) -> Self {
+        Self {
+            source,
+            format: Format::Zstd,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> FanoutSource for Decompress<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    type Item = bytes::Bytes;
+    type Error = DecompressError<S::Error>;
+    /// Always `None`, but still asks the source, which may send its request or fail there.
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        self.source
+            .get_content_length()
+            .await
+            .map_err(DecompressError::Source)?;
+        Ok(None)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let (source_broadcaster, rx) = super::bridge::bridge(&broadcaster);
+        let compressed = tokio_util::io::StreamReader::new(rx.into_stream().map(Ok::<_, std::io::Error>));
+        let decompress = async {
+            use async_compression::tokio::bufread;
+            match self.format {
+                #[cfg(feature = "brotli")]
+                Format::Brotli => broadcast_decoded(bufread::BrotliDecoder::new(compressed), &broadcaster).await,
+                #[cfg(feature = "gzip")]
+                Format::Gzip => {
+                    let mut decoder = bufread::GzipDecoder::new(compressed);
+                    decoder.multiple_members(true);
+                    broadcast_decoded(decoder, &broadcaster).await
+                }
+                #[cfg(feature = "zstd")]
+                Format::Zstd => {
+                    let mut decoder = bufread::ZstdDecoder::new(compressed);
+                    decoder.multiple_members(true);
+                    broadcast_decoded(decoder, &broadcaster).await
+                }
+            }
+            .map_err(DecompressError::Corrupt)
+        };
+        // whichever fails first stops the other; the source's content ends early when it fails, so its error comes
+        // out ahead of the decoder's
+        let source = async {
+            self.source
+                .broadcast(source_broadcaster)
+                .await
+                .map_err(DecompressError::Source)
+        };
+        tokio::try_join!(source, decompress)?;
+        Ok(())
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            source: self.source.reset()?,
+            ..self
+        })
+    }
+}
+
+async fn broadcast_decoded<Channel>(
+    decoder: impl tokio::io::AsyncRead,
+    broadcaster: &broadcaster::Broadcaster<Channel>,
+) -> std::io::Result<()>
+where
+    Channel: channel::Channel<Item = bytes::Bytes>,
+{
+    let decoded = std::pin::pin!(tokio_util::io::ReaderStream::with_capacity(
+        decoder,
+        super::DEFAULT_CHUNK_SIZE,
+    ));
+    broadcaster.broadcast_from_result_stream(decoded).await
+}
@@ -0,0 +1,110 @@
+use std::{ops::Range, path::PathBuf};
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{broadcaster, channel};
+
+/// Broadcasts a file, or a byte range of it, as [`bytes::Bytes`] chunks.
+///
+/// The content length comes from the file's metadata, and resetting reopens the file, so it can be retried as often
+/// as needed. Resuming reopens it too, skipping what was already broadcast; the content length is then what's left.
+pub struct FileSource {
+    path: PathBuf,
+    range: Range<u64>,
+    chunk_size: usize,
+    // how far into the range a resumed source starts
+    resumed_from: u64,
+    // opened to get the content length, then read from by the broadcast
+    file: Option<tokio::fs::File>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            range: 0..u64::MAX,
+            chunk_size: super::DEFAULT_CHUNK_SIZE,
+            resumed_from: 0,
+            file: None,
+        }
+    }
+
+    /// Starts reading at `offset` and carries on to the end of the file.
+    pub fn with_offset(self, offset: u64) -> Self {
+        Self {
+            range: offset..self.range.end,
+            ..self
+        }
+    }
+
+    /// Only reads `range`; a range that runs past the end of the file stops at the end.
+    pub fn with_range(self, range: Range<u64>) -> Self {
+        Self { range, ..self }
+    }
+
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        Self { chunk_size, ..self }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    async fn open(&mut self) -> Result<&mut tokio::fs::File, std::io::Error> {
+        match &mut self.file {
+            Some(file) => Ok(file),
+            file @ None => Ok(file.insert(tokio::fs::File::open(&self.path).await?)),
+        }
+    }
+
+    /// The part of the range that's still to be read from a file of `file_len` bytes.
+    fn clamped_range(&self, file_len: u64) -> Range<u64> {
+        let end = std::cmp::min(self.range.end, file_len);
+        let start = self.range.start.saturating_add(self.resumed_from);
+        std::cmp::min(start, end)..end
+    }
+}
+
+impl super::FanoutSource for FileSource {
+    type Item = bytes::Bytes;
+    type Error = std::io::Error;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        let file_len = self.open().await?.metadata().await?.len();
+        let range = self.clamped_range(file_len);
+        Ok(Some(range.end - range.start))
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        self.open().await?;
+        let mut file = self.file.take().expect("the file was just opened");
+        let range = self.clamped_range(file.metadata().await?.len());
+        file.seek(std::io::SeekFrom::Start(range.start)).await?;
+        let reader = file.take(range.end - range.start);
+        broadcaster
+            .broadcast_from_result_stream(tokio_util::io::ReaderStream::with_capacity(
+                reader,
+                self.chunk_size,
+            ))
+            .await
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            resumed_from: 0,
+            file: None,
+            ..self
+        })
+    }
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        Some(Self {
+            resumed_from: offset,
+            file: None,
+            ..self
+        })
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::OnceLock;
+
+use futures::TryStreamExt;
+use reqwest::{StatusCode, header};
+
+use crate::{broadcaster, channel};
+
+/// The client every [`HttpSource`] uses unless it's given its own, so they all share one connection pool.
+fn shared_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpError {
+    #[error("http request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("unexpected http status {0}")]
+    Status(StatusCode),
+    /// The server sent the whole resource in response to a resumed request, because it no longer matches the
+    /// `ETag` the broadcast started with.
+    #[error("the resource changed since the broadcast started")]
+    Changed,
+    /// The server sent the whole resource in response to a resumed request even though its `ETag` still matches,
+    /// because it doesn't support range requests.
+    #[error("the server doesn't support range requests")]
+    RangeNotSupported,
+    /// The server answered a resumed request with a part of the resource that doesn't start where the broadcast
+    /// left off.
+    #[error("expected the content range to start at byte {expected}, got {content_range:?}")]
+    RangeMismatch {
+        expected: u64,
+        content_range: Option<header::HeaderValue>,
+    },
+}
+
+/// Broadcasts the body of a GET request as [`bytes::Bytes`] chunks.
+///
+/// The content length comes from the `Content-Length` header, and resetting sends the request again. Resuming asks
+/// for the rest of the body with a `Range` request, guarded by `If-Range` so that a resource that has changed since
+/// isn't spliced onto the old one; that needs the first response to have a strong `ETag`.
+#[derive(Debug)]
+pub struct HttpSource {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    headers: header::HeaderMap,
+    // how far into the body a resumed source starts, and what it has to still match to resume
+    resumed_from: u64,
+    etag: Option<header::HeaderValue>,
+    // sent to get the content length, then read from by the broadcast
+    response: Option<reqwest::Response>,
+}
+
+impl HttpSource {
+    pub fn new(url: reqwest::Url) -> Self {
+        Self {
+            client: shared_client().clone(),
+            url,
+            headers: header::HeaderMap::new(),
+            resumed_from: 0,
+            etag: None,
+            response: None,
+        }
+    }
+
+    /// Sends the requests through `client` instead of the client shared by every source.
+    pub fn with_client(self, client: reqwest::Client) -> Self {
+        Self { client, ..self }
+    }
+
+    /// Adds a header to every request, on top of the ones needed to resume.
+    pub fn with_header(mut self, name: header::HeaderName, value: header::HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn with_headers(mut self, headers: header::HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn url(&self) -> &reqwest::Url {
+        &self.url
+    }
+
+    /// Sends the request, unless the response is already waiting to be read.
+    async fn send(&mut self) -> Result<&reqwest::Response, HttpError> {
+        if self.response.is_none() {
+            self.response = Some(self.send_request().await?);
+        }
+        Ok(self.response.as_ref().expect("the request was just sent"))
+    }
+
+    async fn send_request(&mut self) -> Result<reqwest::Response, HttpError> {
+        let mut request = self.client.get(self.url.clone()).headers(self.headers.clone());
+        if self.resumed_from > 0 {
+            let etag = self
+                .etag
+                .clone()
+                .expect("only sources with an etag can be resumed");
+            request = request
+                .header(header::RANGE, format!("bytes={}-", self.resumed_from))
+                .header(header::IF_RANGE, etag);
+        }
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT if self.resumed_from > 0 => {
+                let content_range = response.headers().get(header::CONTENT_RANGE);
+                if content_range.and_then(content_range_start) != Some(self.resumed_from) {
+                    return Err(HttpError::RangeMismatch {
+                        expected: self.resumed_from,
+                        content_range: content_range.cloned(),
+                    });
+                }
+            }
+            // If-Range only falls back to the whole resource once the etag stops matching
+            StatusCode::OK if self.resumed_from > 0 => {
+                return Err(if response.headers().get(header::ETAG) == self.etag.as_ref() {
+                    HttpError::RangeNotSupported
+                } else {
+                    HttpError::Changed
+                });
+            }
+            status if status.is_success() => {
+                // weak etags can't be used with If-Range
+                self.etag = response
+                    .headers()
+                    .get(header::ETAG)
+                    .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+                    .cloned();
+            }
+            status => return Err(HttpError::Status(status)),
+        }
+        Ok(response)
+    }
+}
+
+/// The first byte of a `Content-Range` like `bytes 100-199/200`.
+fn content_range_start(content_range: &header::HeaderValue) -> Option<u64> {
+    let (start, _) = content_range
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?;
+    start.parse().ok()
+}
+
+impl super::FanoutSource for HttpSource {
+    type Item = bytes::Bytes;
+    type Error = HttpError;
+    /// What's left of the body once the source has resumed.
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        Ok(self.send().await?.content_length())
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let response = match self.response.take() {
+            Some(response) => response,
+            None => self.send_request().await?,
+        };
+        broadcaster
+            .broadcast_from_result_stream(response.bytes_stream().map_err(HttpError::from))
+            .await
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            resumed_from: 0,
+            etag: None,
+            response: None,
+            ..self
+        })
+    }
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        if offset == 0 {
+            return self.reset();
+        }
+        self.etag.is_some().then(|| Self {
+            resumed_from: offset,
+            response: None,
+            ..self
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{broadcaster, channel};
+
+mod bridge;
+mod concat;
+#[cfg(any(feature = "brotli", feature = "gzip", feature = "zstd"))]
+mod decompress;
+mod file;
+#[cfg(feature = "http")]
+mod http;
+mod reader;
+mod rechunk;
+mod retrying;
+mod spooled;
+mod verified;
+
+pub use concat::{Concat, ConcatError};
+#[cfg(any(feature = "brotli", feature = "gzip", feature = "zstd"))]
+pub use decompress::{Decompress, DecompressError};
+pub use file::FileSource;
+#[cfg(feature = "http")]
+pub use http::{HttpError, HttpSource};
+pub use reader::{DEFAULT_CHUNK_SIZE, ReaderSource};
+pub use rechunk::Rechunk;
+pub use retrying::{RetryBudget, RetryPolicy, Retrying};
+pub use spooled::{DEFAULT_MEMORY_THRESHOLD, SpoolError, Spooled};
+#[cfg(feature = "crc32c")]
+pub use verified::Crc32c;
+#[cfg(feature = "md5")]
+pub use verified::Md5;
+#[cfg(feature = "sha256")]
+pub use verified::Sha256;
+pub use verified::{Digest, DigestMismatch, Verified, VerifyError};
+
+pub trait FanoutSource: Send + Sync + 'static + Sized {
+    type Item;
+    type Error;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error>;
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>;
+    fn reset(self) -> Option<Self>;
+    /// Like [`Self::reset`], but picks the content up `offset` bytes in rather than from the start, for sources that
+    /// can skip ahead. Offsets count from the start of the content however many times the source has resumed.
+    fn resume_from(self, _offset: u64) -> Option<Self> {
+        None
+    }
+}
+
+impl<S, T, E> FanoutSource for S
+where
+    S: futures::Stream<Item = Result<T, E>> + Unpin + Send + Sync + 'static + Sized,
+{
+    type Item = T;
+    type Error = E;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        Ok(None)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = T>,
+    {
+        broadcaster.broadcast_from_result_stream(self).await
+    }
+    fn reset(self) -> Option<Self> {
+        None
+    }
+}
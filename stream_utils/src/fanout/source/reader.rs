@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use crate::{broadcaster, channel};
+
+/// The chunk size [`ReaderSource`] uses unless told otherwise.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+type ReaderFactory<R> = Arc<dyn Fn() -> R + Send + Sync>;
+
+/// Broadcasts anything that implements [`tokio::io::AsyncRead`] as [`bytes::Bytes`] chunks.
+///
+/// Chunks are at most the chunk size; a read that returns less is broadcast as it is, without waiting to fill the
+/// chunk. The read buffer is reused once every consumer has dropped the chunks taken from it.
+pub struct ReaderSource<R> {
+    reader: Option<R>,
+    chunk_size: usize,
+    content_length: Option<u64>,
+    factory: Option<ReaderFactory<R>>,
+}
+
+impl<R> ReaderSource<R> {
+    /// A source that can't be reset, since there's no way to get the reader back.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Some(reader),
+            ..Self::empty()
+        }
+    }
+
+    /// A source that makes a new reader every time it's reset.
+    pub fn from_factory(factory: impl Fn() -> R + Send + Sync + 'static) -> Self {
+        Self {
+            reader: Some(factory()),
+            factory: Some(Arc::new(factory)),
+            ..Self::empty()
+        }
+    }
+
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        Self { chunk_size, ..self }
+    }
+
+    /// Passed on to consumers as is; the reader isn't checked against it.
+    pub fn with_content_length(self, content_length: u64) -> Self {
+        Self {
+            content_length: Some(content_length),
+            ..self
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            reader: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            content_length: None,
+            factory: None,
+        }
+    }
+}
+
+impl<R> super::FanoutSource for ReaderSource<R>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + Sync + 'static,
+{
+    type Item = bytes::Bytes;
+    type Error = std::io::Error;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        Ok(self.content_length)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let reader = self.reader.take().ok_or_else(|| {
+            std::io::Error::other("the reader was already broadcast; reset the source to read it again")
+        })?;
+        broadcaster
+            .broadcast_from_result_stream(tokio_util::io::ReaderStream::with_capacity(
+                reader,
+                self.chunk_size,
+            ))
+            .await
+    }
+    fn reset(self) -> Option<Self> {
+        let factory = self.factory?;
+        Some(Self {
+            reader: Some(factory()),
+            factory: Some(factory),
+            ..self
+        })
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+/// Coalesces a source's small chunks and splits its big ones, so consumers get chunks of a steady size whatever the
+/// source happens to read.
+///
+/// Chunks that are already the right size are passed on as they are, and splitting doesn't copy, so only the bytes
+/// that get coalesced are copied. The last chunk can come up short, as can one flushed by the flush timeout.
+pub struct Rechunk<S> {
+    source: S,
+    min_size: usize,
+    max_size: usize,
+    flush_timeout: Option<Duration>,
+}
+
+impl<S> Rechunk<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    /// Chunks of exactly `chunk_size` bytes.
+    pub fn fixed(source: S, chunk_size: usize) -> Self {
+        Self::between(source, chunk_size, chunk_size)
+    }
+
+    /// Chunks of at least `min_size` and at most `max_size` bytes.
+    pub fn between(source: S, min_size: usize, max_size: usize) -> Self {
+        assert!(min_size > 0, "chunk size must be positive");
+        assert!(min_size <= max_size, "the minimum chunk size can't be above the maximum");
+        Self {
+            source,
+            min_size,
+            max_size,
+            flush_timeout: None,
+        }
+    }
+
+    /// Broadcasts a short chunk once it has waited `flush_timeout` for more bytes, so a slow source can't hold back
+    /// what it has already read.
+    pub fn with_flush_timeout(self, flush_timeout: Duration) -> Self {
+        Self {
+            flush_timeout: Some(flush_timeout),
+            ..self
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> FanoutSource for Rechunk<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    type Item = bytes::Bytes;
+    type Error = S::Error;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        self.source.get_content_length().await
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let (source_broadcaster, mut rx) = super::bridge::bridge(&broadcaster);
+        let mut chunker = Chunker::new(self.min_size, self.max_size);
+        let flush_timeout = self.flush_timeout;
+        // owns the receiver, so the source's broadcasts stop being held up once this is done
+        let rechunk = async move {
+            let mut ready = Vec::new();
+            let mut flush_at = None;
+            loop {
+                let flush = async {
+                    match flush_at {
+                        Some(flush_at) => tokio::time::sleep_until(flush_at).await,
+                        None => futures::future::pending().await,
+                    }
+                };
+                // whether the bytes that were pending went out, and the stream is done
+                let (emptied, finished) = tokio::select! {
+                    biased;
+                    () = flush => {
+                        ready.extend(chunker.flush());
+                        (true, false)
+                    }
+                    chunk = rx.recv() => match chunk {
+                        Some(chunk) => (chunker.push(chunk, &mut ready), false),
+                        None => {
+                            ready.extend(chunker.flush());
+                            (true, true)
+                        }
+                    },
+                };
+                for chunk in ready.drain(..) {
+                    if broadcaster.broadcast(chunk).await.is_err() {
+                        return;
+                    }
+                }
+                if finished {
+                    return;
+                }
+                // the timeout runs from when the oldest pending byte came in, which is now if the last ones went out
+                flush_at = match flush_timeout {
+                    Some(flush_timeout) if !chunker.is_empty() => flush_at
+                        .filter(|_| !emptied)
+                        .or_else(|| Some(tokio::time::Instant::now() + flush_timeout)),
+                    _ => None,
+                };
+            }
+        };
+        let (result, ()) = tokio::join!(self.source.broadcast(source_broadcaster), rechunk);
+        result
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            source: self.source.reset()?,
+            ..self
+        })
+    }
+    /// Rechunking doesn't change the content, so offsets are the source's own.
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        Some(Self {
+            source: self.source.resume_from(offset)?,
+            ..self
+        })
+    }
+}
+
+/// Holds on to bytes until there are enough for a chunk.
+struct Chunker {
+    min_size: usize,
+    max_size: usize,
+    pending: bytes::BytesMut,
+}
+
+impl Chunker {
+    fn new(min_size: usize, max_size: usize) -> Self {
+        Self {
+            min_size,
+            max_size,
+            pending: bytes::BytesMut::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Adds the chunks `chunk` completes to `ready`, and returns whether that took the bytes that were pending, so
+    /// whatever is pending now came in with `chunk`.
+    fn push(&mut self, mut chunk: bytes::Bytes, ready: &mut Vec<bytes::Bytes>) -> bool {
+        let emptied = !self.pending.is_empty();
+        if emptied {
+            // only copy what it takes to get the pending bytes up to size, so the rest of the chunk can be split off
+            let missing = self.min_size - self.pending.len();
+            self.pending
+                .extend_from_slice(&chunk.split_to(std::cmp::min(missing, chunk.len())));
+            if self.pending.len() < self.min_size {
+                return false;
+            }
+            ready.push(self.pending.split().freeze());
+        }
+        while chunk.len() > self.max_size {
+            ready.push(chunk.split_to(self.max_size));
+        }
+        if chunk.len() >= self.min_size {
+            ready.push(chunk);
+        } else {
+            self.pending.extend_from_slice(&chunk);
+        }
+        emptied
+    }
+
+    fn flush(&mut self) -> Option<bytes::Bytes> {
+        (!self.pending.is_empty()).then(|| self.pending.split().freeze())
+    }
+}
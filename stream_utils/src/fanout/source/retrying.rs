@@ -0,0 +1,330 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+/// How a [`Retrying`] source retries.
+///
+/// Backoff doubles with every retry, from `initial` up to `max`, and by default each wait is jittered to somewhere
+/// between zero and the backoff so that sources failing together don't retry together.
+pub struct RetryPolicy<E> {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retryable: fn(&E) -> bool,
+    budget: Option<RetryBudget>,
+}
+
+impl<E> RetryPolicy<E> {
+    /// Retries any error, up to `max_attempts` attempts in all, backing off from 100ms up to 10s.
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "a retry policy needs at least one attempt");
+        Self {
+            max_attempts,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            retryable: |_| true,
+            budget: None,
+        }
+    }
+
+    pub fn with_backoff(self, initial: Duration, max: Duration) -> Self {
+        Self {
+            initial_backoff: initial,
+            max_backoff: max,
+            ..self
+        }
+    }
+
+    pub fn without_jitter(self) -> Self {
+        Self {
+            jitter: false,
+            ..self
+        }
+    }
+
+    /// Only retries the errors `retryable` accepts; the rest are returned straight away.
+    pub fn with_retryable(self, retryable: fn(&E) -> bool) -> Self {
+        Self { retryable, ..self }
+    }
+
+    /// Also spends every retry from `budget`, and stops retrying once it's used up.
+    pub fn with_budget(self, budget: RetryBudget) -> Self {
+        Self {
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// How long to wait before the given retry, counting from one.
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry - 1))
+            .min(self.max_backoff);
+        if !self.jitter {
+            return backoff;
+        }
+        use std::hash::BuildHasher;
+        // every RandomState is keyed differently, which is all the randomness jitter needs
+        let random = std::collections::hash_map::RandomState::new().hash_one(());
+        backoff.mul_f64(random as f64 / u64::MAX as f64)
+    }
+}
+
+impl<E> Clone for RetryPolicy<E> {
+    fn clone(&self) -> Self {
+        Self {
+            budget: self.budget.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("jitter", &self.jitter)
+            .field("budget", &self.budget)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A number of retries shared by every policy it's given to, so a widespread outage can't turn into a retry storm.
+///
+/// Clones share the same budget.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    remaining: Arc<AtomicU64>,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u64) -> Self {
+        Self {
+            remaining: Arc::new(AtomicU64::new(retries)),
+        }
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining.load(Ordering::Acquire)
+    }
+
+    fn try_spend(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |remaining| remaining.checked_sub(1))
+            .is_ok()
+    }
+}
+
+/// Retries a source's failures according to a [`RetryPolicy`], within a single fanout.
+///
+/// A failure before anything was broadcast resets the source. Once items are out, consumers already have them, so
+/// the source is only retried if it can resume after them, which takes [`Self::with_resume`].
+pub struct Retrying<S>
+where
+    S: FanoutSource,
+{
+    // only missing if the source couldn't be reset for a retry, which ends the fanout
+    source: Option<S>,
+    policy: RetryPolicy<S::Error>,
+    byte_len: Option<fn(&S::Item) -> usize>,
+    failed_attempts: u32,
+    // where in the content the broadcast starts, once the source has been resumed from outside
+    start_offset: u64,
+    // stops the backoff while retrying the content length, before there's a broadcaster to cancel it
+    cancellation_token: Option<broadcaster::CancellationToken>,
+}
+
+impl<S> Retrying<S>
+where
+    S: FanoutSource,
+{
+    pub fn new(source: S, policy: RetryPolicy<S::Error>) -> Self {
+        Self {
+            source: Some(source),
+            policy,
+            byte_len: None,
+            failed_attempts: 0,
+            start_offset: 0,
+            cancellation_token: None,
+        }
+    }
+
+    /// Stops retrying the content length once `cancellation_token` is cancelled, returning the last error; pass the
+    /// token the fanout is driven with. The broadcast itself always stops retrying when the fanout is cancelled.
+    pub fn with_cancellation_token(self, cancellation_token: broadcaster::CancellationToken) -> Self {
+        Self {
+            cancellation_token: Some(cancellation_token),
+            ..self
+        }
+    }
+
+    /// Counts the bytes broadcast, so that a failure after some were broadcast can resume after them.
+    pub fn with_resume(self) -> Self
+    where
+        S::Item: broadcaster::ByteLen,
+    {
+        Self {
+            byte_len: Some(broadcaster::ByteLen::byte_len),
+            ..self
+        }
+    }
+
+    /// The attempts that have failed so far.
+    pub fn failed_attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    /// `None` if the source was lost to a failed reset.
+    pub fn into_inner(self) -> Option<S> {
+        self.source
+    }
+
+    fn source(&mut self) -> &mut S {
+        self.source
+            .as_mut()
+            .expect("a source that couldn't be reset isn't used again")
+    }
+
+    /// Readies the source for another attempt and returns how long to wait first, or `None` if `error` can't be
+    /// retried.
+    fn prepare_retry(&mut self, error: &S::Error, broadcast: &Broadcast) -> Option<Duration> {
+        self.failed_attempts += 1;
+        if self.failed_attempts >= self.policy.max_attempts || !(self.policy.retryable)(error) {
+            return None;
+        }
+        let offset = match (broadcast.items, broadcast.bytes) {
+            (0, _) => self.start_offset,
+            (_, Some(bytes)) => self.start_offset + bytes,
+            (_, None) => return None,
+        };
+        if self
+            .policy
+            .budget
+            .as_ref()
+            .is_some_and(|budget| !budget.try_spend())
+        {
+            return None;
+        }
+        let source = self.source.take().expect("the source is only taken here");
+        // a source that can't be reset or resumed is lost, which ends the fanout
+        self.source = Some(match offset {
+            0 => source.reset(),
+            offset => source.resume_from(offset),
+        }?);
+
+        let backoff = self.policy.backoff(self.failed_attempts);
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            failed_attempts = self.failed_attempts,
+            offset,
+            backoff_ms = backoff.as_millis(),
+            "retrying fanout source",
+        );
+        Some(backoff)
+    }
+
+    /// Runs one attempt through a broadcaster of its own, so that the fanout's broadcaster outlives it.
+    async fn broadcast_attempt<Channel>(
+        &mut self,
+        fanout_broadcaster: &broadcaster::Broadcaster<Channel>,
+        broadcast: &mut Broadcast,
+    ) -> Result<(), S::Error>
+    where
+        Channel: channel::Channel<Item = S::Item>,
+    {
+        let byte_len = self.byte_len;
+        super::bridge::broadcast_through(self.source(), fanout_broadcaster, |item: &S::Item| {
+            broadcast.items += 1;
+            broadcast.bytes = byte_len.map(|byte_len| broadcast.bytes.unwrap_or(0) + byte_len(item) as u64);
+        })
+        .await
+    }
+}
+
+impl<S> FanoutSource for Retrying<S>
+where
+    S: FanoutSource,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        loop {
+            match self.source().get_content_length().await {
+                Ok(content_length) => return Ok(content_length),
+                Err(error) => {
+                    let Some(backoff) = self.prepare_retry(&error, &Broadcast::default()) else {
+                        return Err(error);
+                    };
+                    match &self.cancellation_token {
+                        Some(cancellation_token) => tokio::select! {
+                            biased;
+                            _ = cancellation_token.cancelled() => return Err(error),
+                            _ = tokio::time::sleep(backoff) => {}
+                        },
+                        None => tokio::time::sleep(backoff).await,
+                    }
+                }
+            }
+        }
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let mut broadcast = Broadcast::default();
+        loop {
+            let Err(error) = self.broadcast_attempt(&broadcaster, &mut broadcast).await else {
+                return Ok(());
+            };
+            let Some(backoff) = self.prepare_retry(&error, &broadcast) else {
+                return Err(error);
+            };
+            tokio::select! {
+                biased;
+                _ = broadcaster.get_cancellation_token().cancelled() => return Ok(()),
+                _ = tokio::time::sleep(backoff) => {}
+            }
+        }
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            source: self.source?.reset(),
+            failed_attempts: 0,
+            start_offset: 0,
+            ..self
+        })
+        .filter(|retrying| retrying.source.is_some())
+    }
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        Some(Self {
+            source: Some(self.source?.resume_from(offset)?),
+            failed_attempts: 0,
+            start_offset: offset,
+            ..self
+        })
+    }
+}
+
+/// What's been broadcast across attempts.
+#[derive(Debug, Default)]
+struct Broadcast {
+    items: u64,
+    // `None` unless bytes are counted
+    bytes: Option<u64>,
+}
@@ -0,0 +1,295 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+/// How much a [`Spooled`] source keeps in memory before spilling to disk, unless told otherwise.
+pub const DEFAULT_MEMORY_THRESHOLD: usize = 8 * 1024 * 1024;
+
+// keeps the spool files of one process apart
+static NEXT_SPOOL_FILE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(thiserror::Error, Debug)]
+pub enum SpoolError<E> {
+    #[error(transparent)]
+    Source(E),
+    #[error("couldn't replay the spooled content")]
+    Replay(#[source] std::io::Error),
+}
+
+/// Tees a source into a spool as it's broadcast, so that once it has been broadcast in full, resets replay the spool
+/// instead of going back to the source.
+///
+/// The spool stays in memory up to the memory threshold, then spills to a temp file that's removed along with the
+/// source. If writing to the spool fails, the broadcast carries on without it, and resets go back to the source, as
+/// they do when the source broadcast a different number of bytes than its content length.
+pub struct Spooled<S> {
+    state: State<S>,
+    memory_threshold: usize,
+    spool_dir: PathBuf,
+}
+
+enum State<S> {
+    // `None` once spooling has failed
+    Teeing { source: S, spool: Option<Spool> },
+    Replaying { spool: Spool, offset: u64 },
+}
+
+impl<S> Spooled<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    pub fn new(source: S) -> Self {
+        Self {
+            state: State::Teeing {
+                source,
+                spool: Some(Spool::default()),
+            },
+            memory_threshold: DEFAULT_MEMORY_THRESHOLD,
+            spool_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Spills to disk once the spool holds more than `memory_threshold` bytes; zero spools straight to disk.
+    pub fn with_memory_threshold(self, memory_threshold: usize) -> Self {
+        Self {
+            memory_threshold,
+            ..self
+        }
+    }
+
+    /// Where to put the spool file, instead of [`std::env::temp_dir`].
+    pub fn with_spool_dir(self, spool_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            spool_dir: spool_dir.into(),
+            ..self
+        }
+    }
+
+    /// Whether broadcasts come from the spool rather than the source.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.state, State::Replaying { .. })
+    }
+}
+
+impl<S> FanoutSource for Spooled<S>
+where
+    S: FanoutSource<Item = bytes::Bytes>,
+{
+    type Item = bytes::Bytes;
+    type Error = SpoolError<S::Error>;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        match &mut self.state {
+            State::Teeing { source, spool } => {
+                let content_length = source.get_content_length().await.map_err(SpoolError::Source)?;
+                if let Some(spool) = spool {
+                    // counts what the spool already holds from before a resume
+                    spool.expected_len = content_length.map(|content_length| spool.len + content_length);
+                }
+                Ok(content_length)
+            }
+            State::Replaying { spool, offset } => Ok(Some(spool.len - *offset)),
+        }
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        match &mut self.state {
+            State::Teeing { source, spool } => {
+                let tee = Tee {
+                    spool,
+                    memory_threshold: self.memory_threshold,
+                    spool_dir: &self.spool_dir,
+                };
+                super::bridge::broadcast_through(source, &broadcaster, tee)
+                    .await
+                    .map_err(SpoolError::Source)?;
+                // a cancelled broadcast didn't get to the end of the content
+                if !broadcaster.get_cancellation_token().is_cancelled()
+                    && let Some(filled) = spool
+                    && filled.finish().await.is_err()
+                {
+                    *spool = None;
+                }
+                Ok(())
+            }
+            State::Replaying { spool, offset } => spool.replay(*offset, &broadcaster).await,
+        }
+    }
+    /// Replays the spool once the source has been broadcast in full, and resets the source otherwise.
+    fn reset(self) -> Option<Self> {
+        let state = match self.state {
+            State::Teeing {
+                spool: Some(spool), ..
+            }
+            | State::Replaying { spool, .. } if spool.complete => State::Replaying { spool, offset: 0 },
+            State::Teeing { source, .. } => State::Teeing {
+                source: source.reset()?,
+                spool: Some(Spool::default()),
+            },
+            State::Replaying { .. } => unreachable!("only a complete spool is replayed"),
+        };
+        Some(Self { state, ..self })
+    }
+    /// Skips ahead in the spool once the source has been broadcast in full. Before that, the source resumes, and the
+    /// spool carries on only if it stops right at the offset.
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        let state = match self.state {
+            State::Teeing {
+                spool: Some(spool), ..
+            }
+            | State::Replaying { spool, .. } if spool.complete => {
+                if offset > spool.len {
+                    return None;
+                }
+                State::Replaying { spool, offset }
+            }
+            State::Teeing { source, spool } => State::Teeing {
+                source: source.resume_from(offset)?,
+                spool: spool.filter(|spool| spool.len == offset),
+            },
+            State::Replaying { .. } => unreachable!("only a complete spool is replayed"),
+        };
+        Some(Self { state, ..self })
+    }
+}
+
+/// What's been teed off the source: in memory until it passes the threshold, then all in a file.
+#[derive(Default)]
+struct Spool {
+    len: u64,
+    chunks: Vec<bytes::Bytes>,
+    file: Option<SpoolFile>,
+    // what the source said the whole content comes to
+    expected_len: Option<u64>,
+    // holds the whole content
+    complete: bool,
+}
+
+impl Spool {
+    async fn write(&mut self, chunk: &bytes::Bytes, memory_threshold: usize, spool_dir: &Path) -> std::io::Result<()> {
+        self.len += chunk.len() as u64;
+        let file = match &mut self.file {
+            Some(file) => file,
+            None if self.len <= memory_threshold as u64 => {
+                self.chunks.push(chunk.clone());
+                return Ok(());
+            }
+            file @ None => file.insert(SpoolFile::create(spool_dir, std::mem::take(&mut self.chunks)).await?),
+        };
+        file.file.write_all(chunk).await
+    }
+
+    async fn finish(&mut self) -> std::io::Result<()> {
+        // a source that was cut short, or ran long, doesn't get replayed as if that were the content
+        if self.expected_len.is_some_and(|expected_len| expected_len != self.len) {
+            return Ok(());
+        }
+        if let Some(file) = &mut self.file {
+            // replays read through a handle of their own
+            file.file.flush().await?;
+        }
+        self.complete = true;
+        Ok(())
+    }
+
+    async fn replay<Channel, E>(
+        &self,
+        offset: u64,
+        broadcaster: &broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), SpoolError<E>>
+    where
+        Channel: channel::Channel<Item = bytes::Bytes>,
+    {
+        let Some(file) = &self.file else {
+            let mut skip = offset;
+            let chunks = self.chunks.iter().filter_map(|chunk| {
+                let len = chunk.len() as u64;
+                if skip >= len {
+                    skip -= len;
+                    return None;
+                }
+                let chunk = chunk.slice(skip as usize..);
+                skip = 0;
+                Some(chunk)
+            });
+            broadcaster.broadcast_from_stream(futures::stream::iter(chunks)).await;
+            return Ok(());
+        };
+        let mut reader = tokio::fs::File::open(&file.path).await.map_err(SpoolError::Replay)?;
+        reader
+            .seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(SpoolError::Replay)?;
+        broadcaster
+            .broadcast_from_result_stream(tokio_util::io::ReaderStream::with_capacity(
+                reader,
+                super::DEFAULT_CHUNK_SIZE,
+            ))
+            .await
+            .map_err(SpoolError::Replay)
+    }
+}
+
+/// A spool's temp file, removed when the spool is dropped.
+struct SpoolFile {
+    path: PathBuf,
+    file: tokio::fs::File,
+}
+
+impl SpoolFile {
+    /// Starts the file off with what was spooled in memory.
+    async fn create(spool_dir: &Path, chunks: Vec<bytes::Bytes>) -> std::io::Result<Self> {
+        let path = spool_dir.join(format!(
+            "stream_utils-spool-{}-{}",
+            std::process::id(),
+            NEXT_SPOOL_FILE.fetch_add(1, Ordering::Relaxed),
+        ));
+        let file = tokio::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+        let mut spool_file = Self { path, file };
+        for chunk in chunks {
+            spool_file.file.write_all(&chunk).await?;
+        }
+        Ok(spool_file)
+    }
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Writes what the source broadcasts to the spool, and drops the spool if that fails.
+struct Tee<'a> {
+    spool: &'a mut Option<Spool>,
+    memory_threshold: usize,
+    spool_dir: &'a Path,
+}
+
+impl super::bridge::Inspect<bytes::Bytes> for Tee<'_> {
+    async fn inspect(&mut self, chunk: &bytes::Bytes) {
+        let Some(spool) = self.spool.as_mut() else {
+            return;
+        };
+        if let Err(_error) = spool.write(chunk, self.memory_threshold, self.spool_dir).await {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %_error, "stopped spooling fanout source");
+            *self.spool = None;
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{broadcaster, channel};
+
+use super::FanoutSource;
+
+/// A hash algorithm a [`Verified`] source can check its content against.
+///
+/// [`Sha256`], [`Crc32c`] and [`Md5`] are behind the `sha256`, `crc32c` and `md5` features.
+pub trait Digest: Default + Send + Sync + 'static {
+    fn update(&mut self, bytes: &[u8]);
+    fn finalize(self) -> Vec<u8>;
+}
+
+#[cfg(feature = "sha256")]
+#[derive(Debug, Clone, Default)]
+pub struct Sha256(sha2::Sha256);
+
+#[cfg(feature = "sha256")]
+impl Digest for Sha256 {
+    fn update(&mut self, bytes: &[u8]) {
+        sha2::Digest::update(&mut self.0, bytes);
+    }
+    fn finalize(self) -> Vec<u8> {
+        sha2::Digest::finalize(self.0).to_vec()
+    }
+}
+
+/// The checksum as four big-endian bytes.
+#[cfg(feature = "crc32c")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc32c(u32);
+
+#[cfg(feature = "crc32c")]
+impl Digest for Crc32c {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0 = crc32c::crc32c_append(self.0, bytes);
+    }
+    fn finalize(self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+#[cfg(feature = "md5")]
+#[derive(Debug, Clone, Default)]
+pub struct Md5(md5::Md5);
+
+#[cfg(feature = "md5")]
+impl Digest for Md5 {
+    fn update(&mut self, bytes: &[u8]) {
+        md5::Digest::update(&mut self.0, bytes);
+    }
+    fn finalize(self) -> Vec<u8> {
+        md5::Digest::finalize(self.0).to_vec()
+    }
+}
+
+/// The digest of what the source broadcast doesn't match the expected one.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("expected a digest of {}, but the source's content has {}", hex(expected), hex(actual))]
+pub struct DigestMismatch {
+    pub expected: Vec<u8>,
+    pub actual: Vec<u8>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError<E> {
+    #[error(transparent)]
+    Source(E),
+    #[error(transparent)]
+    DigestMismatch(#[from] DigestMismatch),
+}
+
+/// Hashes everything the source broadcasts, and fails the broadcast with a [`DigestMismatch`] if the digest isn't the
+/// expected one, so consumers see the source fail and the egress gets an error instead of the end of the stream.
+///
+/// The last item still reaches consumers before the digest can be checked, so they have to wait for the end of the
+/// stream before trusting what they received.
+pub struct Verified<S, D> {
+    source: S,
+    expected: Vec<u8>,
+    // carried over when resuming, since the bytes before the offset were hashed by the earlier broadcast
+    digest: D,
+    hashed: u64,
+}
+
+impl<S, D> Verified<S, D>
+where
+    D: Digest,
+{
+    pub fn new(source: S, expected: impl Into<Vec<u8>>) -> Self {
+        Self {
+            source,
+            expected: expected.into(),
+            digest: D::default(),
+            hashed: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S, D> FanoutSource for Verified<S, D>
+where
+    S: FanoutSource,
+    S::Item: AsRef<[u8]>,
+    D: Digest,
+{
+    type Item = S::Item;
+    type Error = VerifyError<S::Error>;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        self.source.get_content_length().await.map_err(VerifyError::Source)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let (digest, hashed) = (&mut self.digest, &mut self.hashed);
+        super::bridge::broadcast_through(&mut self.source, &broadcaster, |item: &S::Item| {
+            digest.update(item.as_ref());
+            *hashed += item.as_ref().len() as u64;
+        })
+        .await
+        .map_err(VerifyError::Source)?;
+        // a cancelled broadcast didn't get to the end of the content
+        if broadcaster.get_cancellation_token().is_cancelled() {
+            return Ok(());
+        }
+        let actual = std::mem::take(&mut self.digest).finalize();
+        if actual != self.expected {
+            return Err(DigestMismatch {
+                expected: self.expected.clone(),
+                actual,
+            }
+            .into());
+        }
+        Ok(())
+    }
+    fn reset(self) -> Option<Self> {
+        Some(Self {
+            source: self.source.reset()?,
+            digest: D::default(),
+            hashed: 0,
+            ..self
+        })
+    }
+    /// Only resumes right where the hashing left off, since the digest can't go back over bytes it has seen.
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        if offset != self.hashed {
+            return None;
+        }
+        Some(Self {
+            source: self.source.resume_from(offset)?,
+            ..self
+        })
+    }
+}
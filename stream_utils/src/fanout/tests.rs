@@ -0,0 +1,1309 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{broadcaster, channel};
+
+#[derive(Debug, PartialEq)]
+struct Record(u32);
+
+struct RecordCollector;
+
+impl super::consumer::FanoutConsumer for RecordCollector {
+    type Item = Arc<Record>;
+    type Output = Vec<Arc<Record>>;
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        let mut records = Vec::new();
+        while let Some(record) = rx.recv().await {
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_shares_non_clone_source_items() {
+    let source = futures::stream::iter(
+        (0..3).map(|i| Ok::<_, std::convert::Infallible>(Record(i))),
+    );
+    let (_, result) = super::StreamFanout::new(source, RecordCollector)
+        .into_driver()
+        .with_broadcaster_channel(channel::ArcChannel::new(
+            tokio::sync::mpsc::channel::<Arc<Record>>,
+        ))
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    let records = result.unwrap().unwrap();
+    assert_eq!(
+        records.iter().map(|record| record.0).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+}
+
+/// Processes a limited number of bytes before failing, acknowledging each item it processes.
+struct FailingConsumer {
+    fail_after: usize,
+}
+
+impl super::consumer::FanoutConsumer for FailingConsumer {
+    type Item = bytes::Bytes;
+    type Output = usize;
+    type Error = String;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        let mut processed = 0;
+        while let Some(chunk) = rx.recv().await {
+            if processed >= self.fail_after {
+                cancellation_token.cancel();
+                return Err("failed".to_string());
+            }
+            processed += chunk.len();
+            rx.ack(rx.sequence().unwrap());
+        }
+        Ok(processed)
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_reports_acknowledgements() {
+    let source = futures::stream::iter(
+        (0..10).map(|_| Ok::<_, std::convert::Infallible>(bytes::Bytes::from_static(&[0u8; 4]))),
+    );
+    let (used, result) = super::StreamFanout::new(source, FailingConsumer { fail_after: 8 })
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_ack_mode()
+        .drive()
+        .await;
+    assert!(result.unwrap().is_err());
+    assert!(used.cancellation_reason().is_some());
+    assert_eq!(used.low_watermark(), Some(2));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_pause_stops_pulling_from_source() {
+    let pulled = Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let source = futures::stream::iter(0..3).map({
+        let pulled = pulled.clone();
+        move |i| {
+            pulled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>(Record(i))
+        }
+    });
+    let driver = super::StreamFanout::new(source, RecordCollector)
+        .into_driver()
+        .with_broadcaster_channel(channel::ArcChannel::new(
+            tokio::sync::mpsc::channel::<Arc<Record>>,
+        ))
+        .with_broadcaster_buffer_size(1);
+    let pause_token = driver.pause_token();
+    pause_token.pause();
+    let ((_, result), _) = tokio::join!(driver.drive(), async {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        assert_eq!(pulled.load(std::sync::atomic::Ordering::SeqCst), 0);
+        pause_token.resume();
+    });
+    assert_eq!(result.unwrap().unwrap().len(), 3);
+    assert_eq!(pulled.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+/// Takes a second over each chunk.
+struct SlowChunkCounter;
+
+impl super::consumer::FanoutConsumer for SlowChunkCounter {
+    type Item = bytes::Bytes;
+    type Output = usize;
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        let mut chunks = 0;
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            chunks += 1;
+        }
+        Ok(chunks)
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_wrapped_source_prefetches() {
+    use super::source::Concat;
+
+    let pulled = Arc::new(std::sync::atomic::AtomicU32::new(0));
+    let source = futures::stream::iter(0..20).map({
+        let pulled = pulled.clone();
+        move |_| {
+            pulled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>(bytes::Bytes::from_static(b"0"))
+        }
+    });
+    let drive = super::StreamFanout::new(Concat::new([source]), SlowChunkCounter)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_prefetch_depth(4)
+        .drive();
+    let ((_, result), ()) = tokio::join!(drive, async {
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        // five items between the consumer and the wrapped source's broadcast, four prefetched and one waiting for room
+        assert_eq!(pulled.load(std::sync::atomic::Ordering::SeqCst), 10);
+    });
+    assert_eq!(result.unwrap().unwrap(), 20);
+}
+
+/// Counts items, and records how the stream ended, even if the fanout fails.
+#[derive(Default)]
+struct CompletionRecorder {
+    completion: std::sync::OnceLock<Option<broadcaster::Completion>>,
+}
+
+impl super::consumer::FanoutConsumer for CompletionRecorder {
+    type Item = bytes::Bytes;
+    type Output = usize;
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        let mut received = 0;
+        while rx.recv().await.is_some() {
+            received += 1;
+        }
+        let _ = self.completion.set(rx.completion());
+        Ok(received)
+    }
+}
+
+/// Fails partway through without going through the broadcaster's stream helpers.
+struct FailingSource;
+
+impl super::source::FanoutSource for FailingSource {
+    type Item = bytes::Bytes;
+    type Error = String;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        Ok(None)
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        let _ = broadcaster.broadcast(bytes::Bytes::from_static(b"partial")).await;
+        drop(broadcaster);
+        Err("connection reset".to_string())
+    }
+    fn reset(self) -> Option<Self> {
+        None
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_consumers_see_source_failure() {
+    let completion_recorder = CompletionRecorder::default();
+    let (_, result) = super::StreamFanout::new(FailingSource, &completion_recorder)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert!(matches!(
+        completion_recorder.completion.get(),
+        Some(Some(broadcaster::Completion::Failed))
+    ));
+
+    let source = futures::stream::iter(
+        (0..3).map(|_| Ok::<_, String>(bytes::Bytes::from_static(b"chunk"))),
+    );
+    let completion_recorder = CompletionRecorder::default();
+    let (_, result) = super::StreamFanout::new(source, &completion_recorder)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap(), 3);
+    assert!(matches!(
+        completion_recorder.completion.get(),
+        Some(Some(broadcaster::Completion::Finished))
+    ));
+}
+
+/// Collects every chunk it receives.
+struct ChunkCollector;
+
+impl super::consumer::FanoutConsumer for ChunkCollector {
+    type Item = bytes::Bytes;
+    type Output = Vec<bytes::Bytes>;
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        Ok(chunks)
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_reader_source_chunks_and_resets() {
+    use super::source::FanoutSource;
+
+    const DATA: &[u8] = b"0123456789";
+    let source = super::source::ReaderSource::from_factory(|| std::io::Cursor::new(DATA))
+        .with_chunk_size(4)
+        .with_content_length(DATA.len() as u64);
+    let (used, result) = super::StreamFanout::new(source, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    let chunks = result.unwrap().unwrap();
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 4), "chunks: {chunks:?}");
+    assert_eq!(chunks.concat(), DATA);
+
+    let (mut source, consumers) = used.into_parts();
+    assert_eq!(source.get_content_length().await.unwrap(), Some(DATA.len() as u64));
+    let source = source.reset().expect("a source with a factory can be reset");
+    let (_, result) = super::StreamFanout::new(source, consumers)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), DATA);
+
+    let source = super::source::ReaderSource::new(std::io::Cursor::new(DATA));
+    assert!(source.reset().is_none());
+}
+
+#[tokio::test]
+async fn test_fanout_file_source_reads_ranges_and_reopens() {
+    use super::source::FanoutSource;
+
+    const DATA: &[u8] = b"0123456789";
+    let path = std::env::temp_dir().join(format!("stream_utils_file_source_{}", std::process::id()));
+    tokio::fs::write(&path, DATA).await.unwrap();
+
+    let source = super::source::FileSource::new(&path).with_chunk_size(4);
+    let (used, result) = super::StreamFanout::new(source, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    let chunks = result.unwrap().unwrap();
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 4), "chunks: {chunks:?}");
+    assert_eq!(chunks.concat(), DATA);
+
+    // resetting reopens the file, and the range applies to both the content length and the read
+    let (source, consumers) = used.into_parts();
+    let mut source = source.reset().expect("a file source can always be reset").with_range(3..7);
+    assert_eq!(source.get_content_length().await.unwrap(), Some(4));
+    let (_, result) = super::StreamFanout::new(source, consumers)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), &DATA[3..7]);
+
+    let mut source = super::source::FileSource::new(&path).with_offset(8);
+    assert_eq!(source.get_content_length().await.unwrap(), Some(2));
+    let mut source = super::source::FileSource::new(&path).with_offset(20);
+    assert_eq!(source.get_content_length().await.unwrap(), Some(0));
+
+    tokio::fs::remove_file(&path).await.unwrap();
+}
+
+/// Appends every chunk it receives to what it received on earlier attempts.
+#[derive(Default)]
+struct ByteAppender {
+    received: std::sync::Mutex<Vec<u8>>,
+}
+
+impl super::consumer::FanoutConsumer for ByteAppender {
+    type Item = bytes::Bytes;
+    type Output = ();
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        while let Some(chunk) = rx.recv().await {
+            self.received.lock().unwrap().extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+}
+
+/// Broadcasts its content two bytes at a time, failing once it gets to `fail_at` unless it's been resumed.
+struct FlakySource {
+    content: &'static [u8],
+    offset: usize,
+    fail_at: Option<usize>,
+}
+
+impl super::source::FanoutSource for FlakySource {
+    type Item = bytes::Bytes;
+    type Error = String;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        Ok(Some((self.content.len() - self.offset) as u64))
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        for start in (self.offset..self.content.len()).step_by(2) {
+            if self.fail_at == Some(start) {
+                return Err("connection reset".to_string());
+            }
+            let end = std::cmp::min(start + 2, self.content.len());
+            let _ = broadcaster.broadcast(bytes::Bytes::from_static(&self.content[start..end])).await;
+        }
+        Ok(())
+    }
+    fn reset(self) -> Option<Self> {
+        None
+    }
+    fn resume_from(self, offset: u64) -> Option<Self> {
+        Some(Self {
+            offset: offset as usize,
+            fail_at: None,
+            ..self
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_resumes_from_consumer_progress() {
+    const DATA: &[u8] = b"0123456789";
+    let appender = ByteAppender::default();
+    let source = FlakySource {
+        content: DATA,
+        offset: 0,
+        fail_at: Some(6),
+    };
+    let (used, result) = super::StreamFanout::new(source, &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_progress_tracking()
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert_eq!(used.resume_offset(), Some(6));
+
+    let resumed = used.resume().expect("the source can resume");
+    assert_eq!(resumed.start_offset(), 6);
+    let (used, result) = resumed
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_progress_tracking()
+        .drive()
+        .await;
+    result.unwrap().unwrap();
+    // offsets keep counting from the start of the content
+    assert_eq!(used.resume_offset(), Some(DATA.len() as u64));
+    assert_eq!(*appender.received.lock().unwrap(), DATA);
+
+    // without progress tracking there's nothing to resume from
+    let source = FlakySource {
+        content: DATA,
+        offset: 0,
+        fail_at: Some(4),
+    };
+    let (used, _) = super::StreamFanout::new(source, &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(used.resume().is_none());
+}
+
+/// Appends what it receives until it has `stop_at` bytes, then stops receiving.
+struct StoppingAppender {
+    received: std::sync::Mutex<Vec<u8>>,
+    stop_at: std::sync::atomic::AtomicUsize,
+}
+
+impl super::consumer::FanoutConsumer for StoppingAppender {
+    type Item = bytes::Bytes;
+    type Output = ();
+    type Error = std::convert::Infallible;
+    async fn consume_from_fanout<Rx>(
+        &self,
+        mut rx: Rx,
+        _cancellation_token: broadcaster::CancellationToken,
+        _content_length: Option<u64>,
+    ) -> Result<Self::Output, Self::Error>
+    where
+        Rx: channel::receiver::Receiver<Item = Self::Item>,
+    {
+        while self.received.lock().unwrap().len() < self.stop_at.load(std::sync::atomic::Ordering::Acquire)
+            && let Some(chunk) = rx.recv().await
+        {
+            self.received.lock().unwrap().extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_resume_skips_what_each_consumer_received() {
+    const DATA: &[u8] = b"0123456789";
+    let slow = StoppingAppender {
+        received: std::sync::Mutex::default(),
+        stop_at: std::sync::atomic::AtomicUsize::new(4),
+    };
+    let (egress_tx, mut egress_rx) =
+        tokio::sync::mpsc::channel::<Result<bytes::Bytes, super::egress::GenericError>>(16);
+    // chunks of four don't line up with where the egress left off, so the resumed broadcast has to split one
+    let source = super::source::Rechunk::fixed(
+        FlakySource {
+            content: DATA,
+            offset: 0,
+            fail_at: Some(6),
+        },
+        4,
+    );
+    let (used, result) = super::StreamFanout::new(source, &slow)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_egress_tx(egress_tx.clone())
+        .with_progress_tracking()
+        .drive()
+        .await;
+    assert!(result.is_err());
+    let offsets = used.progress().unwrap().iter().map(|progress| progress.offset).collect::<Vec<_>>();
+    assert_eq!(offsets, [4, 6]);
+
+    slow.stop_at.store(usize::MAX, std::sync::atomic::Ordering::Release);
+    let (used, result) = used
+        .resume()
+        .expect("the source can resume")
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_egress_tx(egress_tx)
+        .with_progress_tracking()
+        .drive()
+        .await;
+    result.unwrap().unwrap();
+    assert_eq!(used.resume_offset(), Some(DATA.len() as u64));
+
+    // each got the bytes it was missing, and none twice
+    assert_eq!(*slow.received.lock().unwrap(), DATA);
+    let mut egressed = Vec::new();
+    while let Some(chunk) = egress_rx.recv().await {
+        if let Ok(chunk) = chunk {
+            egressed.extend_from_slice(&chunk);
+        }
+    }
+    assert_eq!(egressed, DATA);
+}
+
+/// Serves `content` at `/content` to requests with the right authorization, one connection per request.
+///
+/// How [`serve_http`] answers a range request whose `If-Range` matches.
+#[cfg(feature = "http")]
+#[derive(Clone, Copy)]
+enum ServedRange {
+    Honoured,
+    /// Sends the whole body, like a server without range support.
+    Ignored,
+    /// Starts this many bytes before the requested range, and says so in `Content-Range`.
+    Early(usize),
+}
+
+/// The first response carries `first_etag` and hangs up after `truncate_first` bytes of the body, if set; later ones
+/// carry `etag`. Ranges are only considered if `If-Range` matches the current etag.
+#[cfg(feature = "http")]
+async fn serve_http(
+    content: &'static [u8],
+    first_etag: &'static str,
+    etag: &'static str,
+    truncate_first: Option<usize>,
+    served_range: ServedRange,
+) -> reqwest::Url {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/content", listener.local_addr().unwrap()).parse().unwrap();
+    tokio::spawn(async move {
+        for request_index in 0.. {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.split();
+            let mut lines = tokio::io::BufReader::new(reader).lines();
+            let request_line = lines.next_line().await.unwrap().unwrap();
+            let mut headers = std::collections::HashMap::new();
+            while let Some(line) = lines.next_line().await.unwrap().filter(|line| !line.is_empty()) {
+                let (name, value) = line.split_once(':').unwrap();
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+
+            let current_etag = if request_index == 0 { first_etag } else { etag };
+            let start = headers
+                .get("range")
+                .filter(|_| headers.get("if-range").is_some_and(|if_range| if_range == current_etag))
+                .map(|range| range.trim_start_matches("bytes=").trim_end_matches('-').parse::<usize>().unwrap())
+                .and_then(|start| match served_range {
+                    ServedRange::Honoured => Some(start),
+                    ServedRange::Ignored => None,
+                    ServedRange::Early(early) => Some(start - early),
+                });
+            let (status, body, content_range) = if !request_line.starts_with("GET /content ") {
+                ("404 Not Found", &content[..0], String::new())
+            } else if headers.get("authorization").is_none_or(|value| value != "secret") {
+                ("401 Unauthorized", &content[..0], String::new())
+            } else if let Some(start) = start {
+                let content_range = format!("Content-Range: bytes {start}-{}/{}\r\n", content.len() - 1, content.len());
+                ("206 Partial Content", &content[start..], content_range)
+            } else {
+                ("200 OK", content, String::new())
+            };
+            let head = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\n{content_range}\
+                 ETag: {current_etag}\r\nConnection: close\r\n\r\n",
+                body.len(),
+            );
+            writer.write_all(head.as_bytes()).await.unwrap();
+            let body = match truncate_first {
+                Some(truncated) if request_index == 0 => &body[..truncated],
+                _ => body,
+            };
+            writer.write_all(body).await.unwrap();
+        }
+    });
+    url
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn test_fanout_http_source_checks_status_and_resumes_with_range() {
+    use super::source::{FanoutSource, HttpError, HttpSource};
+
+    const DATA: &[u8] = b"0123456789";
+    let authorization = |source: HttpSource| {
+        source.with_header(
+            reqwest::header::AUTHORIZATION,
+            reqwest::header::HeaderValue::from_static("secret"),
+        )
+    };
+
+    let url = serve_http(DATA, "\"v1\"", "\"v1\"", None, ServedRange::Honoured).await;
+    let (_, result) = super::StreamFanout::new(HttpSource::new(url.clone()), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(matches!(
+        result,
+        Err(super::FanoutError::Source(HttpError::Status(reqwest::StatusCode::UNAUTHORIZED)))
+    ));
+    let mut source = authorization(HttpSource::new(url));
+    assert_eq!(source.get_content_length().await.unwrap(), Some(DATA.len() as u64));
+
+    // the first response hangs up partway through, and the rest comes from a range request
+    let url = serve_http(DATA, "\"v1\"", "\"v1\"", Some(6), ServedRange::Honoured).await;
+    let appender = ByteAppender::default();
+    let (used, result) = super::StreamFanout::new(authorization(HttpSource::new(url)), &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_progress_tracking()
+        .drive()
+        .await;
+    assert!(matches!(result, Err(super::FanoutError::Source(HttpError::Request(_)))));
+    assert_eq!(used.resume_offset(), Some(6));
+    let (_, result) = used
+        .resume()
+        .expect("the response had a strong etag")
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    result.unwrap().unwrap();
+    assert_eq!(*appender.received.lock().unwrap(), DATA);
+
+    // a resource that changed in between is sent whole, which can't be spliced onto what was received
+    let url = serve_http(DATA, "\"v1\"", "\"v2\"", Some(6), ServedRange::Honoured).await;
+    let appender = ByteAppender::default();
+    let (used, _) = super::StreamFanout::new(authorization(HttpSource::new(url)), &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_progress_tracking()
+        .drive()
+        .await;
+    let (_, result) = used
+        .resume()
+        .unwrap()
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(matches!(result, Err(super::FanoutError::Source(HttpError::Changed))));
+    assert_eq!(*appender.received.lock().unwrap(), &DATA[..6]);
+
+    // a server that ignores the range, or answers with the wrong one, can't be resumed from either
+    for served_range in [ServedRange::Ignored, ServedRange::Early(2)] {
+        let url = serve_http(DATA, "\"v1\"", "\"v1\"", Some(6), served_range).await;
+        let appender = ByteAppender::default();
+        let (used, _) = super::StreamFanout::new(authorization(HttpSource::new(url)), &appender)
+            .into_driver()
+            .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+            .with_broadcaster_buffer_size(1)
+            .with_progress_tracking()
+            .drive()
+            .await;
+        let (_, result) = used
+            .resume()
+            .unwrap()
+            .into_driver()
+            .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+            .with_broadcaster_buffer_size(1)
+            .drive()
+            .await;
+        let Err(super::FanoutError::Source(error)) = result else {
+            panic!("resuming should fail");
+        };
+        match served_range {
+            ServedRange::Ignored => assert!(matches!(error, HttpError::RangeNotSupported)),
+            _ => assert!(matches!(error, HttpError::RangeMismatch { expected: 6, content_range: Some(_) })),
+        }
+        assert_eq!(*appender.received.lock().unwrap(), &DATA[..6]);
+    }
+}
+
+/// Fails every attempt before broadcasting anything, counting the attempts; with `failing_length`, it fails to get
+/// the content length instead.
+#[derive(Default)]
+struct AlwaysFailingSource {
+    attempts: Arc<std::sync::atomic::AtomicU32>,
+    failing_length: bool,
+}
+
+impl super::source::FanoutSource for AlwaysFailingSource {
+    type Item = bytes::Bytes;
+    type Error = String;
+    async fn get_content_length(&mut self) -> Result<Option<u64>, Self::Error> {
+        if !self.failing_length {
+            return Ok(None);
+        }
+        self.attempts.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+        Err("connection refused".to_string())
+    }
+    async fn broadcast<Channel>(
+        &mut self,
+        _broadcaster: broadcaster::Broadcaster<Channel>,
+    ) -> Result<(), Self::Error>
+    where
+        Channel: channel::Channel<Item = Self::Item>,
+    {
+        self.attempts.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+        Err("connection refused".to_string())
+    }
+    fn reset(self) -> Option<Self> {
+        Some(self)
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_retrying_source_backs_off_within_policy() {
+    use super::source::{RetryBudget, RetryPolicy, Retrying};
+
+    let source = AlwaysFailingSource::default();
+    let attempts = source.attempts.clone();
+    let policy = RetryPolicy::new(3)
+        .with_backoff(tokio::time::Duration::from_millis(100), tokio::time::Duration::from_millis(150))
+        .without_jitter();
+    let start = tokio::time::Instant::now();
+    let (_, result) = super::StreamFanout::new(Retrying::new(source, policy), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::Acquire), 3);
+    // 100ms, then 200ms capped to 150ms
+    assert_eq!(start.elapsed(), tokio::time::Duration::from_millis(250));
+
+    // errors the policy doesn't accept, and a spent budget, stop the retries early
+    let source = AlwaysFailingSource::default();
+    let attempts = source.attempts.clone();
+    let policy = RetryPolicy::new(3).with_retryable(|error: &String| !error.contains("refused"));
+    let (_, result) = super::StreamFanout::new(Retrying::new(source, policy), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::Acquire), 1);
+
+    let budget = RetryBudget::new(1);
+    let source = AlwaysFailingSource::default();
+    let attempts = source.attempts.clone();
+    let policy = RetryPolicy::new(5).with_budget(budget.clone());
+    let (_, result) = super::StreamFanout::new(Retrying::new(source, policy), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::Acquire), 2);
+    assert_eq!(budget.remaining(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_retrying_source_stops_retrying_the_length_once_cancelled() {
+    use super::source::{FanoutSource, RetryPolicy, Retrying};
+
+    let source = AlwaysFailingSource {
+        failing_length: true,
+        ..Default::default()
+    };
+    let attempts = source.attempts.clone();
+    let policy = RetryPolicy::new(10)
+        .with_backoff(tokio::time::Duration::from_secs(1), tokio::time::Duration::from_secs(1))
+        .without_jitter();
+    let cancellation_token = broadcaster::CancellationToken::default();
+    let mut retrying = Retrying::new(source, policy).with_cancellation_token(cancellation_token.clone());
+    let start = tokio::time::Instant::now();
+    let (result, ()) = tokio::join!(retrying.get_content_length(), async {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        cancellation_token.cancel();
+    });
+    // the second backoff is cut short, and the last error is returned
+    assert_eq!(result, Err("connection refused".to_string()));
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::Acquire), 2);
+    assert_eq!(start.elapsed(), tokio::time::Duration::from_millis(1500));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_retrying_source_only_retries_broadcast_items_by_resuming() {
+    use super::source::{RetryPolicy, Retrying};
+
+    const DATA: &[u8] = b"0123456789";
+    let flaky_source = || FlakySource {
+        content: DATA,
+        offset: 0,
+        fail_at: Some(6),
+    };
+
+    // consumers already have the first items, so starting over isn't an option
+    let appender = ByteAppender::default();
+    let (_, result) = super::StreamFanout::new(Retrying::new(flaky_source(), RetryPolicy::new(3)), &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    assert_eq!(*appender.received.lock().unwrap(), &DATA[..6]);
+
+    let appender = ByteAppender::default();
+    let source = Retrying::new(flaky_source(), RetryPolicy::new(3)).with_resume();
+    let (used, result) = super::StreamFanout::new(source, &appender)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    result.unwrap().unwrap();
+    assert_eq!(*appender.received.lock().unwrap(), DATA);
+    assert_eq!(used.into_parts().0.failed_attempts(), 1);
+}
+
+#[tokio::test]
+async fn test_fanout_strict_content_length_fails_truncated_sources() {
+    const DATA: &[u8] = b"0123456789";
+    let truncated = || {
+        super::source::ReaderSource::new(std::io::Cursor::new(DATA)).with_content_length(20)
+    };
+
+    // content lengths are only a hint by default
+    let (_, result) = super::StreamFanout::new(truncated(), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), DATA);
+
+    let completion_recorder = CompletionRecorder::default();
+    let (egress_tx, mut egress_rx) =
+        tokio::sync::mpsc::channel::<Result<bytes::Bytes, super::egress::GenericError>>(4);
+    let (_, result) = super::StreamFanout::new(truncated(), &completion_recorder)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_egress_tx(egress_tx)
+        .with_strict_content_length()
+        .drive()
+        .await;
+    let Err(super::FanoutError::LengthMismatch(length_mismatch)) = result else {
+        panic!("expected a length mismatch");
+    };
+    assert_eq!(
+        length_mismatch,
+        super::LengthMismatch {
+            expected: 20,
+            actual: DATA.len() as u64,
+        }
+    );
+    assert!(matches!(
+        completion_recorder.completion.get(),
+        Some(Some(broadcaster::Completion::Failed))
+    ));
+    let mut egress = Vec::new();
+    while let Some(item) = egress_rx.recv().await {
+        egress.push(item);
+    }
+    assert!(egress.last().unwrap().is_err());
+
+    // a source that delivers what it promised passes
+    let source = super::source::ReaderSource::new(std::io::Cursor::new(DATA))
+        .with_content_length(DATA.len() as u64);
+    let (_, result) = super::StreamFanout::new(source, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_strict_content_length()
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), DATA);
+}
+
+/// Adds up the bytes, which is all the tests need from a digest.
+#[derive(Default)]
+struct ByteSum(u8);
+
+impl super::source::Digest for ByteSum {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |sum, byte| sum.wrapping_add(*byte));
+    }
+    fn finalize(self) -> Vec<u8> {
+        vec![self.0]
+    }
+}
+
+#[tokio::test]
+async fn test_fanout_verified_source_fails_on_digest_mismatch() {
+    use super::source::{DigestMismatch, ReaderSource, Verified, VerifyError};
+
+    const DATA: &[u8] = b"0123456789";
+    let sum = DATA.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    let source = || ReaderSource::new(std::io::Cursor::new(DATA)).with_chunk_size(4);
+
+    let verified = Verified::<_, ByteSum>::new(source(), [sum]);
+    let (_, result) = super::StreamFanout::new(verified, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), DATA);
+
+    let completion_recorder = CompletionRecorder::default();
+    let (egress_tx, mut egress_rx) =
+        tokio::sync::mpsc::channel::<Result<bytes::Bytes, super::egress::GenericError>>(4);
+    let verified = Verified::<_, ByteSum>::new(source(), [sum.wrapping_add(1)]);
+    let (_, result) = super::StreamFanout::new(verified, &completion_recorder)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_egress_tx(egress_tx)
+        .drive()
+        .await;
+    let Err(super::FanoutError::Source(VerifyError::DigestMismatch(digest_mismatch))) = result else {
+        panic!("expected a digest mismatch");
+    };
+    assert_eq!(
+        digest_mismatch,
+        DigestMismatch {
+            expected: vec![sum.wrapping_add(1)],
+            actual: vec![sum],
+        }
+    );
+    assert!(matches!(
+        completion_recorder.completion.get(),
+        Some(Some(broadcaster::Completion::Failed))
+    ));
+    let mut egress = Vec::new();
+    while let Some(item) = egress_rx.recv().await {
+        egress.push(item);
+    }
+    assert!(egress.last().unwrap().is_err());
+}
+
+#[cfg(all(feature = "sha256", feature = "crc32c", feature = "md5"))]
+#[tokio::test]
+async fn test_fanout_verified_source_digests() {
+    use super::source::{Crc32c, Md5, ReaderSource, Sha256, Verified};
+
+    async fn verify<D: super::source::Digest>(content: &'static [u8], expected: &str) -> bool {
+        let expected = (0..expected.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&expected[index..index + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        let source = ReaderSource::new(std::io::Cursor::new(content)).with_chunk_size(2);
+        let (_, result) = super::StreamFanout::new(Verified::<_, D>::new(source, expected), ChunkCollector)
+            .into_driver()
+            .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+            .with_broadcaster_buffer_size(1)
+            .drive()
+            .await;
+        result.is_ok()
+    }
+
+    assert!(verify::<Sha256>(b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").await);
+    assert!(verify::<Crc32c>(b"123456789", "e3069283").await);
+    assert!(verify::<Md5>(b"abc", "900150983cd24fb0d6963f7d28e17f72").await);
+    assert!(!verify::<Md5>(b"abd", "900150983cd24fb0d6963f7d28e17f72").await);
+}
+
+#[tokio::test]
+async fn test_fanout_concat_source_chains_parts() {
+    use super::source::{Concat, FanoutSource, FileSource, ReaderSource};
+
+    let parts = [&b"0123"[..], b"45", b"6789"];
+    let part = |content: &'static [u8]| {
+        ReaderSource::from_factory(move || std::io::Cursor::new(content))
+            .with_content_length(content.len() as u64)
+    };
+    let mut concat = Concat::new(parts.map(part));
+    assert_eq!(concat.get_content_length().await.unwrap(), Some(10));
+    let (used, result) = super::StreamFanout::new(concat, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert_eq!(result.unwrap().unwrap().concat(), b"0123456789");
+
+    // every part could be reset, so the whole can; one part that can't stops it
+    let (concat, _) = used.into_parts();
+    assert!(concat.reset().is_some());
+    let concat = Concat::new([part(b"01"), ReaderSource::new(std::io::Cursor::new(&b"23"[..]))]);
+    assert!(concat.reset().is_none());
+
+    // an unknown length makes the whole length unknown
+    let mut concat = Concat::new([part(b"01"), ReaderSource::new(std::io::Cursor::new(&b"23"[..]))]);
+    assert_eq!(concat.get_content_length().await.unwrap(), None);
+
+    let missing = std::env::temp_dir().join(format!("stream_utils_concat_missing_{}", std::process::id()));
+    let concat: Concat<_> = [FileSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")), FileSource::new(&missing)].into_iter().collect();
+    let (_, result) = super::StreamFanout::new(concat, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    let Some(super::FanoutError::Source(error)) = result.err() else {
+        panic!("expected the second part to fail");
+    };
+    assert_eq!(error.part, 1);
+    assert_eq!(error.error.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_fanout_spooled_source_replays_from_memory_and_disk() {
+    use super::source::{FanoutSource, ReaderSource, Spooled};
+
+    const DATA: &[u8] = b"0123456789";
+    let spool_dir = std::env::temp_dir().join(format!("stream_utils_spool_{}", std::process::id()));
+    std::fs::create_dir_all(&spool_dir).unwrap();
+    async fn drive<S>(source: Spooled<S>) -> (Spooled<S>, Vec<u8>)
+    where
+        S: FanoutSource<Item = bytes::Bytes>,
+        S::Error: std::fmt::Debug,
+    {
+        let (used, result) = super::StreamFanout::new(source, ChunkCollector)
+            .into_driver()
+            .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+            .with_broadcaster_buffer_size(1)
+            .drive()
+            .await;
+        (used.into_parts().0, result.unwrap().unwrap().concat())
+    }
+
+    // everything past the threshold spills to disk, and a zero threshold spools straight there
+    for memory_threshold in [DATA.len(), 6, 0] {
+        let source = Spooled::new(ReaderSource::new(std::io::Cursor::new(DATA)).with_chunk_size(4))
+            .with_memory_threshold(memory_threshold)
+            .with_spool_dir(&spool_dir);
+        let (source, received) = drive(source).await;
+        assert_eq!(received, DATA);
+        assert!(!source.is_replaying());
+
+        // the reader can't be reset, but the spool can replay it
+        let mut source = source.reset().expect("a complete spool can be replayed");
+        assert!(source.is_replaying());
+        assert_eq!(source.get_content_length().await.unwrap(), Some(DATA.len() as u64));
+        let (source, received) = drive(source).await;
+        assert_eq!(received, DATA);
+
+        let mut source = source.resume_from(6).unwrap();
+        assert_eq!(source.get_content_length().await.unwrap(), Some(4));
+        let (source, received) = drive(source).await;
+        assert_eq!(received, b"6789");
+        assert!(source.resume_from(DATA.len() as u64 + 1).is_none());
+    }
+    // the spool file goes with the source
+    assert_eq!(std::fs::read_dir(&spool_dir).unwrap().count(), 0);
+
+    // a spool that's cut short carries on when the source resumes right where it stopped
+    let flaky = FlakySource {
+        content: DATA,
+        offset: 0,
+        fail_at: Some(4),
+    };
+    let (used, result) = super::StreamFanout::new(Spooled::new(flaky), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(result.is_err());
+    let (source, _) = used.into_parts();
+    let (source, received) = drive(source.resume_from(4).unwrap()).await;
+    assert_eq!(received, b"456789");
+    let (_, received) = drive(source.reset().unwrap()).await;
+    assert_eq!(received, DATA);
+
+    // a truncated source isn't replayed as if it were complete, so strict checking still fails it on a reset
+    let truncated = ReaderSource::new(std::io::Cursor::new(DATA)).with_content_length(20);
+    let (used, result) = super::StreamFanout::new(Spooled::new(truncated), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .with_strict_content_length()
+        .drive()
+        .await;
+    assert!(matches!(result, Err(super::FanoutError::LengthMismatch(_))));
+    let (source, _) = used.into_parts();
+    assert!(source.reset().is_none(), "the reader can't be reset, and the spool is incomplete");
+
+    std::fs::remove_dir(&spool_dir).unwrap();
+}
+
+#[cfg(all(feature = "brotli", feature = "gzip", feature = "zstd"))]
+#[tokio::test]
+async fn test_fanout_decompress_source_decompresses_once() {
+    use async_compression::tokio::bufread;
+    use tokio::io::AsyncReadExt;
+
+    use super::source::{Decompress, DecompressError, FanoutSource, ReaderSource};
+
+    type Source = ReaderSource<std::io::Cursor<Vec<u8>>>;
+
+    async fn compress(mut encoder: impl tokio::io::AsyncRead + Unpin) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        encoder.read_to_end(&mut compressed).await.unwrap();
+        compressed
+    }
+
+    async fn decompress(
+        compressed: Vec<u8>,
+        decompress: fn(Source) -> Decompress<Source>,
+    ) -> Result<Vec<u8>, super::FanoutError<DecompressError<std::io::Error>>> {
+        let source = ReaderSource::new(std::io::Cursor::new(compressed)).with_chunk_size(3);
+        let mut source = decompress(source);
+        assert_eq!(source.get_content_length().await.unwrap(), None);
+        let (_, result) = super::StreamFanout::new(source, ChunkCollector)
+            .into_driver()
+            .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+            .with_broadcaster_buffer_size(1)
+            .drive()
+            .await;
+        result.map(|chunks| chunks.unwrap().concat())
+    }
+
+    let data = b"the quick brown fox jumps over the lazy dog ".repeat(64);
+    let gzip = compress(bufread::GzipEncoder::new(&data[..])).await;
+    let zstd = compress(bufread::ZstdEncoder::new(&data[..])).await;
+    let brotli = compress(bufread::BrotliEncoder::new(&data[..])).await;
+    assert_eq!(decompress(gzip.clone(), Decompress::gzip).await.unwrap(), data);
+    assert_eq!(decompress(zstd.clone(), Decompress::zstd).await.unwrap(), data);
+    assert_eq!(decompress(brotli, Decompress::brotli).await.unwrap(), data);
+
+    // concatenated members and frames decompress to concatenated content
+    assert_eq!(decompress([gzip.clone(), gzip.clone()].concat(), Decompress::gzip).await.unwrap(), [&data[..], &data[..]].concat());
+    assert_eq!(decompress([zstd.clone(), zstd].concat(), Decompress::zstd).await.unwrap(), [&data[..], &data[..]].concat());
+
+    let error = decompress(b"not gzip at all".to_vec(), Decompress::gzip).await.unwrap_err();
+    assert!(matches!(error, super::FanoutError::Source(DecompressError::Corrupt(_))), "error: {error:?}");
+    let error = decompress(gzip[..gzip.len() / 2].to_vec(), Decompress::gzip).await.unwrap_err();
+    assert!(matches!(error, super::FanoutError::Source(DecompressError::Corrupt(_))), "error: {error:?}");
+
+    // a source that fails partway through reports its own error rather than the truncated content's
+    let flaky = FlakySource {
+        content: gzip.leak(),
+        offset: 0,
+        fail_at: Some(4),
+    };
+    let (_, result) = super::StreamFanout::new(Decompress::gzip(flaky), ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    assert!(matches!(
+        result,
+        Err(super::FanoutError::Source(DecompressError::Source(error))) if error == "connection reset"
+    ));
+
+    // the length is never known, but the source is still asked, so it fails there
+    let mut source = Decompress::gzip(AlwaysFailingSource {
+        failing_length: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        source.get_content_length().await,
+        Err(DecompressError::Source(error)) if error == "connection refused"
+    ));
+}
+
+async fn collect_rechunked<S>(source: super::source::Rechunk<S>) -> Vec<bytes::Bytes>
+where
+    S: super::source::FanoutSource<Item = bytes::Bytes>,
+    S::Error: std::fmt::Debug,
+{
+    let (_, result) = super::StreamFanout::new(source, ChunkCollector)
+        .into_driver()
+        .with_broadcaster_channel(tokio::sync::mpsc::channel::<bytes::Bytes>)
+        .with_broadcaster_buffer_size(1)
+        .drive()
+        .await;
+    result.unwrap().unwrap()
+}
+
+fn chunk_stream(
+    chunks: &[&'static [u8]],
+) -> futures::stream::Iter<std::vec::IntoIter<Result<bytes::Bytes, std::convert::Infallible>>> {
+    futures::stream::iter(
+        chunks
+            .iter()
+            .map(|chunk| Ok(bytes::Bytes::from_static(chunk)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[tokio::test]
+async fn test_fanout_rechunk_source_coalesces_and_splits() {
+    use super::source::Rechunk;
+
+    let source = Rechunk::fixed(chunk_stream(&[b"0", b"12", b"3456789abcde", b"f", b"g"]), 4);
+    assert_eq!(collect_rechunked(source).await, [&b"0123"[..], b"4567", b"89ab", b"cdef", b"g"]);
+
+    // chunks that are already within bounds, and the chunks split off big ones, aren't copied
+    const FIRST: &[u8] = b"012345";
+    const BIG: &[u8] = b"0123456789";
+    const FITS: &[u8] = b"abcd";
+    let source = Rechunk::between(chunk_stream(&[FIRST, BIG, b"0", b"1", FITS]), 3, 4);
+    let chunks = collect_rechunked(source).await;
+    assert_eq!(chunks, [&b"0123"[..], b"450", b"1234", b"5678", b"901", b"abcd"]);
+    assert_eq!(chunks[0].as_ptr(), FIRST.as_ptr());
+    assert_eq!(chunks[2].as_ptr(), BIG[1..].as_ptr());
+    assert_eq!(chunks[3].as_ptr(), BIG[5..].as_ptr());
+    assert_eq!(chunks[5].as_ptr(), FITS.as_ptr());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_rechunk_source_flushes_slow_sources() {
+    use futures::StreamExt;
+
+    use super::source::Rechunk;
+
+    // two bytes straight away, and two more after a second
+    let slow_source = || {
+        Box::pin(chunk_stream(&[b"ab", b"cd"]).enumerate().then(|(index, chunk)| async move {
+            tokio::time::sleep(tokio::time::Duration::from_secs(index as u64)).await;
+            chunk
+        }))
+    };
+    let chunks = collect_rechunked(Rechunk::fixed(slow_source(), 4)).await;
+    assert_eq!(chunks, [&b"abcd"[..]]);
+
+    let source = Rechunk::fixed(slow_source(), 4).with_flush_timeout(tokio::time::Duration::from_millis(100));
+    let chunks = collect_rechunked(source).await;
+    assert_eq!(chunks, [&b"ab"[..], b"cd"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fanout_rechunk_source_restarts_flush_timeout_for_new_pending_bytes() {
+    use futures::StreamExt;
+
+    use super::source::Rechunk;
+
+    // "efg" is left pending at 80ms, then completed by "h" at 150ms, before its own timeout runs out
+    let delays_ms = [0, 80, 70];
+    let source = Box::pin(chunk_stream(&[b"ab", b"cdefg", b"h"]).zip(futures::stream::iter(delays_ms)).then(
+        |(chunk, delay_ms)| async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+            chunk
+        },
+    ));
+    let source = Rechunk::fixed(source, 4).with_flush_timeout(tokio::time::Duration::from_millis(100));
+    let chunks = collect_rechunked(source).await;
+    assert_eq!(chunks, [&b"abcd"[..], b"efgh"]);
+}
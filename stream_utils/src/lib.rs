@@ -1,3 +1,6 @@
+// the traits in this crate are deliberately usable with futures that are not `Send`
+#![allow(async_fn_in_trait)]
+
 #[cfg(feature = "broadcaster")]
 pub mod broadcaster;
 pub mod channel;
@@ -107,7 +107,7 @@ pub(crate) fn impl_consumer_group(
                         impl std::future::Future<Output = Self::Output> + 'fanout_consumer_group,
                     )
                     where
-                        Channel: stream_utils::channel::Channel<Item = bytes::Bytes>,
+                        Channel: stream_utils::channel::Channel<Shared = #item_ty>,
                         Channel::Receiver: 'static,
                     {
                         #(#create_consumer_futures)*